@@ -299,3 +299,94 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         self.request(EvmApiMethod::CaptureHostIO, request);
     }
 }
+
+/// Records each request and replies with a fixed response.
+#[cfg(test)]
+struct Recorder {
+    requests: Vec<Vec<u8>>,
+    response: (Vec<u8>, Vec<u8>, u64),
+}
+
+#[cfg(test)]
+impl Recorder {
+    fn new(res: Vec<u8>, data: Vec<u8>, cost: u64) -> Self {
+        let requests = vec![];
+        let response = (res, data, cost);
+        Self { requests, response }
+    }
+}
+
+#[cfg(test)]
+impl RequestHandler<crate::evm::api::VecReader> for Recorder {
+    fn request(
+        &mut self,
+        _req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> (Vec<u8>, crate::evm::api::VecReader, u64) {
+        use crate::evm::api::VecReader;
+        self.requests.push(req_data.as_ref().to_vec());
+        let (res, data, cost) = self.response.clone();
+        (res, VecReader::new(data), cost)
+    }
+}
+
+#[test]
+fn test_emit_log_encoding() {
+    let topics = [Bytes32([1; 32]), Bytes32([2; 32])];
+    let body = b"log data".to_vec();
+    let mut data = topics.concat();
+    data.extend(&body);
+
+    let mut api = EvmApiRequestor::new(Recorder::new(vec![], vec![], 0));
+    api.emit_log(data, topics.len() as u32).unwrap();
+
+    // decode the way the Go side does: a big-endian topic count, the topics, then the data
+    let request = &api.request_handler().requests[0];
+    let (count, rest) = request.split_at(4);
+    let count = u32::from_be_bytes(count.try_into().unwrap()) as usize;
+    let (hashes, rest) = rest.split_at(32 * count);
+    assert_eq!(count, topics.len());
+    assert_eq!(hashes, topics.concat());
+    assert_eq!(rest, body);
+
+    // a non-empty response is the Go side's error message
+    api.request_handler().response.0 = b"write protection".to_vec();
+    let err = api.emit_log(vec![], 0).unwrap_err();
+    assert_eq!(err.to_string(), "write protection");
+}
+
+#[test]
+fn test_create_encoding() {
+    let code = b"init code".to_vec();
+    let endowment = Bytes32([1; 32]);
+    let salt = Bytes32([2; 32]);
+    let address = Bytes20([3; 20]);
+
+    // a successful deployment replies with a 1 followed by the address
+    let mut res = vec![1];
+    res.extend(address);
+    let mut api = EvmApiRequestor::new(Recorder::new(res, b"data".to_vec(), 1000));
+    let (result, data_len, cost) = api.create2(code.clone(), endowment, salt, 50_000);
+    assert_eq!(result.unwrap(), address);
+    assert_eq!((data_len, cost), (4, 1000));
+    assert_eq!(api.get_return_data().slice(), b"data");
+
+    // the request holds the gas, the endowment, the salt if any, and then the code
+    let request = &api.request_handler().requests[0];
+    assert_eq!(request[..8], 50_000_u64.to_be_bytes());
+    assert_eq!(request[8..40], endowment.0);
+    assert_eq!(request[40..72], salt.0);
+    assert_eq!(request[72..], code);
+
+    api.request_handler().requests.clear();
+    api.create1(code.clone(), endowment, 50_000).0.unwrap();
+    assert_eq!(api.request_handler().requests[0][40..], code);
+
+    // failures reply with a 0 followed by the error, and charge the gas consumed
+    let mut res = vec![0];
+    res.extend(b"out of gas");
+    api.request_handler().response = (res, vec![], 50_000);
+    let (result, data_len, cost) = api.create1(code, endowment, 50_000);
+    assert_eq!(result.unwrap_err().to_string(), "out of gas");
+    assert_eq!((data_len, cost), (0, 50_000));
+}
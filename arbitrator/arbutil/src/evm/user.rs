@@ -41,6 +41,14 @@ impl UserOutcome {
     }
 }
 
+impl UserOutcomeKind {
+    /// Whether the outcome consumes all remaining gas regardless of what the meter says.
+    /// Every other outcome, including failures, refunds whatever ink is left.
+    pub fn burns_all_gas(self) -> bool {
+        matches!(self, Self::OutOfStack)
+    }
+}
+
 impl From<&UserOutcome> for UserOutcomeKind {
     fn from(value: &UserOutcome) -> Self {
         use UserOutcome::*;
@@ -89,3 +97,28 @@ impl Display for UserOutcomeKind {
         }
     }
 }
+
+#[test]
+fn test_outcome_kind_values() {
+    // the Go side matches on these, so they must never be renumbered
+    use UserOutcomeKind::*;
+    assert_eq!(u8::from(Success), 0);
+    assert_eq!(u8::from(Revert), 1);
+    assert_eq!(u8::from(Failure), 2);
+    assert_eq!(u8::from(OutOfInk), 3);
+    assert_eq!(u8::from(OutOfStack), 4);
+    assert!(UserOutcomeKind::try_from(5).is_err());
+}
+
+#[test]
+fn test_outcome_data() {
+    // programs that run out of ink or stack never return data, even if they wrote a result
+    use UserOutcome::*;
+    assert_eq!(Success(vec![1]).into_data().1, vec![1]);
+    assert_eq!(Revert(vec![2]).into_data().1, vec![2]);
+    assert_eq!(OutOfInk.into_data(), (UserOutcomeKind::OutOfInk, vec![]));
+    assert_eq!(
+        OutOfStack.into_data(),
+        (UserOutcomeKind::OutOfStack, vec![])
+    );
+}
@@ -249,3 +249,23 @@ impl From<GenericBytes20> for Bytes20 {
         <[u8; 20]>::from(x).into()
     }
 }
+
+#[test]
+fn test_big_endian_words() {
+    // words cross the FFI and EVM in big-endian, so the low-order bytes come last
+    let word = Bytes32::from(0x0102_0304_u32);
+    assert_eq!(word[28..], [1, 2, 3, 4]);
+    assert!(word[..28].iter().all(|&x| x == 0));
+    assert_eq!(Bytes32::from(0x0102_0304_u64), word);
+    assert_eq!(Bytes32::from(0x0102_0304_usize), word);
+
+    let address = Bytes20::from(0x0102_0304_u32);
+    assert_eq!(address[16..], [1, 2, 3, 4]);
+    assert_eq!(Bytes20::from(0x0102_0304_u64), address);
+
+    let value = U256::from(u64::MAX) << 128;
+    let word = Bytes32::from(value);
+    assert_eq!(word[8..16], [0xff; 8]);
+    assert_eq!(U256::from(word), value);
+    assert_eq!(U256::from(Bytes32::from(7_u64)), U256::from(7));
+}
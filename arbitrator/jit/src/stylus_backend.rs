@@ -149,9 +149,10 @@ pub fn exec_wasm(
     let thread = thread::spawn(move || {
         let outcome = instance.run_main(&calldata, config, ink);
 
-        let ink_left = match outcome.as_ref() {
-            Ok(UserOutcome::OutOfStack) => 0, // take all ink when out of stack
-            _ => instance.ink_left().into(),
+        let burns_all_gas = matches!(&outcome, Ok(outcome) if outcome.kind().burns_all_gas());
+        let ink_left = match burns_all_gas {
+            true => 0, // take all ink when out of stack
+            false => instance.ink_left().into(),
         };
 
         let outcome = match outcome {
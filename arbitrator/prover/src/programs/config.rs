@@ -17,7 +17,7 @@ use {
         meter::Meter, start::StartMover, MiddlewareWrapper,
     },
     std::sync::Arc,
-    wasmer::{Cranelift, CraneliftOptLevel, Engine, Store},
+    wasmer::{sys::EngineBuilder, Cranelift, CraneliftOptLevel, Engine, Store, Target},
     wasmer_compiler_singlepass::Singlepass,
 };
 
@@ -72,12 +72,16 @@ impl PricingParams {
         Self { ink_price }
     }
 
+    /// Converts gas to ink, saturating rather than overflowing. Saturation is safe: `u64::MAX`
+    /// ink is more than any program could ever use, since gas is always limited upstream.
     pub fn gas_to_ink(&self, gas: u64) -> u64 {
         gas.saturating_mul(self.ink_price.into())
     }
 
+    /// Converts ink to gas, rounding down so that partial gas is never refunded.
+    /// The ink price is never 0, since ArbOS rejects it when set.
     pub fn ink_to_gas(&self, ink: u64) -> u64 {
-        ink / self.ink_price as u64 // never 0
+        ink / self.ink_price as u64
     }
 }
 
@@ -154,7 +158,21 @@ impl Default for CompileMemoryParams {
 }
 
 impl CompileConfig {
+    /// Gets the config for a given Stylus version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the version is unknown, since a node executing a program of a newer version
+    /// than it supports must stop rather than diverge.
     pub fn version(version: u16, debug_chain: bool) -> Self {
+        match Self::try_version(version, debug_chain) {
+            Some(config) => config,
+            None => panic!("no config exists for Stylus version {version}"),
+        }
+    }
+
+    /// Gets the config for a given Stylus version, if this binary supports it.
+    pub fn try_version(version: u16, debug_chain: bool) -> Option<Self> {
         let mut config = Self::default();
         config.version = version;
         config.debug.debug_funcs = debug_chain;
@@ -173,14 +191,14 @@ impl CompileConfig {
                     memory_copy_ink: 800 / 8,
                 };
             }
-            _ => panic!("no config exists for Stylus version {version}"),
+            _ => return None,
         }
 
-        config
+        Some(config)
     }
 
     #[cfg(feature = "native")]
-    pub fn store(&self) -> Store {
+    fn compiler(&self) -> Box<dyn wasmer::CompilerConfig> {
         let mut compiler: Box<dyn wasmer::CompilerConfig> = match self.debug.cranelift {
             true => {
                 let mut compiler = Cranelift::new();
@@ -210,12 +228,79 @@ impl CompileConfig {
             let counter = Counter::new();
             compiler.push_middleware(Arc::new(MiddlewareWrapper::new(counter)));
         }
+        compiler
+    }
 
-        Store::new(compiler)
+    #[cfg(feature = "native")]
+    pub fn store(&self) -> Store {
+        Store::new(self.compiler())
     }
 
     #[cfg(feature = "native")]
     pub fn engine(&self) -> Engine {
         self.store().engine().clone()
     }
+
+    /// Creates an engine that produces code for another machine. Modules compiled this way
+    /// can be serialized but only instantiated on the target.
+    #[cfg(feature = "native")]
+    pub fn engine_for_target(&self, target: Target) -> Engine {
+        EngineBuilder::new(self.compiler())
+            .set_target(Some(target))
+            .engine()
+            .into()
+    }
+}
+
+#[test]
+fn test_ink_conversion() {
+    let unit = PricingParams::new(1);
+    assert_eq!(unit.gas_to_ink(u64::MAX), u64::MAX);
+    assert_eq!(unit.ink_to_gas(u64::MAX), u64::MAX);
+
+    let pricing = PricingParams::new(10_000);
+    assert_eq!(pricing.gas_to_ink(3), 30_000);
+    assert_eq!(pricing.ink_to_gas(39_999), 3);
+    assert_eq!(pricing.ink_to_gas(9_999), 0);
+
+    // products that overflow saturate instead of wrapping
+    let max_gas = u64::MAX / 10_000;
+    assert_eq!(pricing.gas_to_ink(max_gas), max_gas * 10_000);
+    assert_eq!(pricing.gas_to_ink(max_gas + 1), u64::MAX);
+    assert_eq!(pricing.ink_to_gas(pricing.gas_to_ink(u64::MAX)), max_gas);
+}
+
+#[test]
+fn test_ink_conversion_properties() {
+    let prices = [1, 2, 3, 9_999, 10_000, u32::MAX];
+    let mut gases = vec![0, 1, 2, 9_999, 10_000, u64::MAX - 1, u64::MAX];
+    gases.extend((0..64).map(|i| 1 << i));
+    gases.extend(prices.map(|price| u64::MAX / price as u64));
+    gases.extend(prices.map(|price| u64::MAX / price as u64 + 1));
+    gases.sort_unstable();
+
+    for price in prices {
+        let pricing = PricingParams::new(price);
+        let max_gas = u64::MAX / price as u64;
+
+        for gas in gases.iter().copied() {
+            // round trips never mint gas, and are exact until saturation
+            let round_trip = pricing.ink_to_gas(pricing.gas_to_ink(gas));
+            assert!(round_trip <= gas);
+            assert_eq!(round_trip, gas.min(max_gas));
+        }
+        for pair in gases.windows(2) {
+            assert!(pricing.gas_to_ink(pair[0]) <= pricing.gas_to_ink(pair[1]));
+            assert!(pricing.ink_to_gas(pair[0]) <= pricing.ink_to_gas(pair[1]));
+        }
+    }
+
+    // a higher price buys more ink per gas and less gas per ink
+    for pair in prices.windows(2) {
+        let (cheap, dear) = (PricingParams::new(pair[0]), PricingParams::new(pair[1]));
+        for gas in gases.iter().copied() {
+            assert!(cheap.gas_to_ink(gas) <= dear.gas_to_ink(gas));
+            assert!(cheap.ink_to_gas(gas) >= dear.ink_to_gas(gas));
+        }
+    }
 }
@@ -3,10 +3,14 @@
 
 #![cfg(test)]
 
-use crate::binary;
+use crate::{
+    binary,
+    programs::{meter::pricing_v1, ModuleMod},
+};
 use brotli::Dictionary;
 use eyre::Result;
 use std::path::Path;
+use wasmparser::Operator;
 
 fn as_wasm(wat: &str) -> Vec<u8> {
     let wasm = wasmer::wat2wasm(wat.as_bytes());
@@ -69,3 +73,54 @@ pub fn test_compress() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+pub fn test_dispatch_pricing() -> Result<()> {
+    let wasm = as_wasm(
+        r#"
+        (module
+            (type $binary (func (param i32 i32) (result i32)))
+            (table 1 funcref)
+            (global $calls (mut i32) (i32.const 0))
+            (func (param i32) (result i32)
+                (call_indirect (type $binary) (i32.const 1) (i32.const 2) (local.get 0)))
+            (func (param i32)
+                (block (block (block (br_table 0 1 2 (local.get 0))))))
+            (func
+                (global.set $calls (global.get $calls)))
+        )"#,
+    );
+    let bin = binary::parse(&wasm, Path::new("user"))?;
+    let sigs = bin.all_signatures()?;
+    let ops: Vec<_> = bin.codes.iter().flat_map(|code| &code.expr).collect();
+    let price = |pred: fn(&Operator) -> bool| {
+        let op = ops.iter().find(|op| pred(op)).expect("missing op");
+        pricing_v1(op, &sigs)
+    };
+
+    // indirect calls pay per argument, and jump tables per target
+    assert_eq!(
+        price(|op| matches!(op, Operator::CallIndirect { .. })),
+        13610 + 650 * 2
+    );
+    assert_eq!(
+        price(|op| matches!(op, Operator::BrTable { .. })),
+        2400 + 325 * 2
+    );
+    assert_eq!(price(|op| matches!(op, Operator::GlobalGet { .. })), 225);
+    assert_eq!(price(|op| matches!(op, Operator::GlobalSet { .. })), 575);
+    for op in &ops {
+        assert!(pricing_v1(op, &sigs) > 0, "{op:?} is free");
+    }
+
+    // table mutation isn't supported, so it can never be afforded
+    for op in [
+        Operator::TableGet { table: 0 },
+        Operator::TableSet { table: 0 },
+        Operator::TableGrow { table: 0 },
+        Operator::TableSize { table: 0 },
+    ] {
+        assert_eq!(pricing_v1(&op, &sigs), u64::MAX);
+    }
+    Ok(())
+}
@@ -0,0 +1,14 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+#![no_main]
+
+use arbutil::Bytes32;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // activation must reject bad modules with an error rather than a panic
+    let codehash = Bytes32::default();
+    let mut gas = 10_000_000;
+    let _ = stylus::native::activate(data, &codehash, 1, 128, false, &mut gas);
+});
@@ -0,0 +1,133 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/nitro/blob/master/LICENSE
+
+#![no_main]
+
+use arbitrary::Unstructured;
+use arbutil::{
+    evm::{
+        api::{EvmApiMethod, VecReader},
+        req::{EvmApiRequestor, RequestHandler},
+        EvmData,
+    },
+    Bytes32,
+};
+use libfuzzer_sys::fuzz_target;
+use stylus::{
+    native::{self, NativeInstance},
+    prover::programs::prelude::*,
+    run::RunProgram,
+};
+use wasm_encoder::{ExportKind, ExportSection, Module as EncodedModule, RawSection};
+use wasm_smith::{Config, Module};
+use wasmparser::{Parser, Payload, ValType};
+
+/// Programs have no imports, so the EVM is never consulted.
+struct NoEvm;
+
+impl RequestHandler<VecReader> for NoEvm {
+    fn request(&mut self, _: EvmApiMethod, _: impl AsRef<[u8]>) -> (Vec<u8>, VecReader, u64) {
+        unreachable!("programs without imports make no requests")
+    }
+}
+
+fn config() -> Config {
+    Config {
+        max_imports: 0,
+        min_memories: 1,
+        max_memories: 1,
+        max_tables: 1,
+        max_memory32_pages: 128,
+        min_funcs: 1,
+        max_exports: 0,
+        bulk_memory_enabled: true,
+        multi_value_enabled: true,
+        reference_types_enabled: false,
+        simd_enabled: false,
+        relaxed_simd_enabled: false,
+        saturating_float_to_int_enabled: true,
+        sign_extension_ops_enabled: true,
+        threads_enabled: false,
+        memory64_enabled: false,
+        exceptions_enabled: false,
+        tail_call_enabled: false,
+        ..Config::default()
+    }
+}
+
+/// Exports the memory and the first `(i32) -> i32` func under the names Stylus requires.
+/// Returns `None` for modules without such a func.
+fn with_entrypoint(wasm: &[u8]) -> Option<Vec<u8>> {
+    let mut types = vec![];
+    let mut entrypoint = None;
+    let mut sections = vec![];
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload.ok()?;
+        match &payload {
+            Payload::TypeSection(reader) => {
+                for ty in reader.clone().into_iter_err_on_gc_types() {
+                    let ty = ty.ok()?;
+                    types.push(ty.params() == [ValType::I32] && ty.results() == [ValType::I32]);
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for (func, ty) in reader.clone().into_iter().enumerate() {
+                    if entrypoint.is_none() && *types.get(ty.ok()? as usize)? {
+                        entrypoint = Some(func as u32); // there are no imported funcs
+                    }
+                }
+            }
+            Payload::ExportSection(_) => continue, // replaced by the exports below
+            _ => {}
+        }
+        if let Some((id, range)) = payload.as_section() {
+            sections.push((id, range));
+        }
+    }
+
+    let mut exports = ExportSection::new();
+    exports.export("user_entrypoint", ExportKind::Func, entrypoint?);
+    exports.export("memory", ExportKind::Memory, 0);
+
+    // the export section goes right after the globals, before the start func and beyond
+    let after_exports = |id: u8| matches!(id, 8..=12);
+    let mut module = EncodedModule::new();
+    let mut exported = false;
+    for (id, range) in sections {
+        if !exported && after_exports(id) {
+            module.section(&exports);
+            exported = true;
+        }
+        let data = &wasm[range];
+        module.section(&RawSection { id, data });
+    }
+    if !exported {
+        module.section(&exports);
+    }
+    Some(module.finish())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut data = Unstructured::new(data);
+    let Ok(module) = Module::new(config(), &mut data) else {
+        return;
+    };
+    let Some(wasm) = with_entrypoint(&module.to_bytes()) else {
+        return;
+    };
+
+    let codehash = Bytes32::default();
+    let mut gas = 10_000_000;
+    let Ok((asm, _, _)) = native::activate(&wasm, &codehash, 1, 128, false, &mut gas) else {
+        return;
+    };
+
+    // valid programs must run to some outcome within a small ink budget
+    let compile = CompileConfig::version(1, false);
+    let evm = EvmApiRequestor::new(NoEvm);
+    let instance = unsafe { NativeInstance::deserialize(&asm, compile, evm, EvmData::default()) };
+    let mut instance = instance.expect("failed to instantiate activated program");
+    let config = StylusConfig::new(1, 10_000, 10_000);
+    let _ = instance.run_main(&[], config, 1_000_000);
+});
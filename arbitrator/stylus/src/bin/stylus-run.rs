@@ -0,0 +1,230 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! Runs a Stylus program locally, without a node.
+//!
+//! Storage and transient storage live in memory for the duration of the run, logs are printed,
+//! and calls and creates always fail since there are no other contracts to reach.
+
+use arbutil::{
+    evm::{
+        api::{EvmApiMethod, EvmApiStatus, VecReader},
+        req::{EvmApiRequestor, RequestHandler},
+        user::{UserOutcome, UserOutcomeKind},
+        EvmData, COLD_ACCOUNT_GAS, COLD_SLOAD_GAS, WARM_SLOAD_GAS,
+    },
+    Bytes32,
+};
+use eyre::{Context, Result};
+use prover::programs::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    process,
+};
+use structopt::StructOpt;
+use stylus::{
+    native::{self, NativeInstance},
+    run::RunProgram,
+};
+
+#[derive(StructOpt)]
+#[structopt(name = "stylus-run")]
+struct Opts {
+    /// the program to activate and run, as wasm or wat
+    #[structopt(long, required_unless = "module")]
+    wasm: Option<PathBuf>,
+    /// a module previously written by --compile-only
+    #[structopt(long, conflicts_with = "wasm")]
+    module: Option<PathBuf>,
+    /// write the activated module here instead of running it
+    #[structopt(long, conflicts_with = "module")]
+    compile_only: Option<PathBuf>,
+    /// hex-encoded calldata
+    #[structopt(long, default_value = "")]
+    calldata: String,
+    #[structopt(long, default_value = "10000000")]
+    gas: u64,
+    #[structopt(long, default_value = "1")]
+    version: u16,
+    #[structopt(long, default_value = "262144")]
+    max_depth: u32,
+    #[structopt(long, default_value = "10000")]
+    ink_price: u32,
+    #[structopt(long, default_value = "128")]
+    page_limit: u16,
+    #[structopt(long)]
+    debug: bool,
+}
+
+/// Answers EVM requests from memory.
+#[derive(Default)]
+struct LocalEvm {
+    storage: HashMap<Bytes32, Bytes32>,
+    transient: HashMap<Bytes32, Bytes32>,
+    warm: HashSet<Bytes32>,
+}
+
+impl RequestHandler<VecReader> for LocalEvm {
+    fn request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> (Vec<u8>, VecReader, u64) {
+        use EvmApiMethod::*;
+        let data = req_data.as_ref();
+        let word = |bytes: &[u8]| Bytes32::try_from(bytes).unwrap();
+        let success = vec![EvmApiStatus::Success.into()];
+        let empty = VecReader::new(vec![]);
+
+        match req_type {
+            GetBytes32 => {
+                let key = word(data);
+                let value = self.storage.get(&key).copied().unwrap_or_default();
+                let cost = match self.warm.insert(key) {
+                    true => COLD_SLOAD_GAS,
+                    false => WARM_SLOAD_GAS,
+                };
+                (value.to_vec(), empty, cost)
+            }
+            SetTrieSlots => {
+                let slots = data[8..].chunks_exact(64);
+                let cost = 22100 * slots.len() as u64; // pretend worst case
+                for slot in slots {
+                    self.storage.insert(word(&slot[..32]), word(&slot[32..]));
+                }
+                (success, empty, cost)
+            }
+            GetTransientBytes32 => {
+                let value = self.transient.get(&word(data)).copied();
+                (value.unwrap_or_default().to_vec(), empty, 0)
+            }
+            SetTransientBytes32 => {
+                self.transient.insert(word(&data[..32]), word(&data[32..]));
+                (success, empty, 0)
+            }
+            ContractCall | DelegateCall | StaticCall => {
+                (vec![UserOutcomeKind::Failure.into()], empty, 0)
+            }
+            Create1 | Create2 => {
+                let mut error = vec![0]; // a zero status byte precedes the error
+                error.extend(b"no contracts to create locally");
+                (error, empty, 0)
+            }
+            EmitLog => {
+                let topics = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+                let (topics, log) = data[4..].split_at(32 * topics);
+                let topics: Vec<_> = topics.chunks(32).map(hex::encode).collect();
+                println!("log [{}] {}", topics.join(", "), hex::encode(log));
+                (vec![], empty, 0)
+            }
+            AccountBalance | AccountCodeHash => {
+                (Bytes32::default().to_vec(), empty, COLD_ACCOUNT_GAS)
+            }
+            AccountCode => (vec![], empty, COLD_ACCOUNT_GAS),
+            AddPages | CaptureHostIO => (vec![], empty, 0),
+        }
+    }
+}
+
+/// Reads the module to run, activating the wasm if given one.
+fn load(opts: &Opts) -> Result<Vec<u8>> {
+    if let Some(path) = &opts.module {
+        return std::fs::read(path).wrap_err("failed to read module");
+    }
+    let path = opts.wasm.as_ref().unwrap();
+    let wasm = std::fs::read(path).wrap_err("failed to read wasm")?;
+    let wasm = wasmer::wat2wasm(&wasm)?;
+    let codehash = Bytes32::default();
+    let (version, page_limit, debug) = (opts.version, opts.page_limit, opts.debug);
+    let mut gas = u64::MAX;
+    let (asm, _, info) = native::activate(&wasm, &codehash, version, page_limit, debug, &mut gas)?;
+    println!("activated: footprint {} pages", info.footprint);
+    Ok(asm)
+}
+
+/// Runs the module, returning the outcome and the gas used.
+fn run(opts: &Opts, asm: &[u8]) -> Result<(UserOutcome, u64)> {
+    let compile = CompileConfig::version(opts.version, opts.debug);
+    let calldata = hex::decode(opts.calldata.trim_start_matches("0x"))?;
+    let config = StylusConfig::new(opts.version, opts.max_depth, opts.ink_price);
+    let evm = EvmApiRequestor::new(LocalEvm::default());
+
+    // Safety: the module was just activated or written by a previous activation
+    let mut instance =
+        unsafe { NativeInstance::deserialize(asm, compile, evm, EvmData::default())? };
+    let ink = config.pricing.gas_to_ink(opts.gas);
+    let outcome = instance.run_main(&calldata, config, ink)?;
+
+    let ink_left = match outcome.kind().burns_all_gas() {
+        true => 0,
+        false => instance.ink_left().into(),
+    };
+    let gas_used = opts.gas - config.pricing.ink_to_gas(ink_left);
+    Ok((outcome, gas_used))
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::from_args();
+    let asm = load(&opts)?;
+    if let Some(path) = &opts.compile_only {
+        std::fs::write(path, &asm).wrap_err("failed to write module")?;
+        return Ok(());
+    }
+
+    let (outcome, gas_used) = run(&opts, &asm)?;
+    let status = outcome.kind();
+
+    println!("status: {status}");
+    match &outcome {
+        UserOutcome::Success(data) | UserOutcome::Revert(data) => {
+            println!("output: {}", hex::encode(data))
+        }
+        UserOutcome::Failure(err) => println!("error: {err:?}"),
+        _ => {}
+    }
+    println!("gas used: {gas_used}");
+    process::exit(u8::from(status).into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arbutil::{crypto, evm::api::EvmApi};
+
+    #[test]
+    fn test_keccak() -> Result<()> {
+        let preimage = b"stylus-run";
+        let mut calldata = vec![0x01];
+        calldata.extend(preimage);
+
+        let wasm = "tests/keccak/target/wasm32-unknown-unknown/release/keccak.wasm";
+        let calldata = hex::encode(calldata);
+        let args = [
+            "stylus-run",
+            "--wasm",
+            wasm,
+            "--calldata",
+            calldata.as_str(),
+        ];
+        let opts = Opts::from_iter(args);
+
+        let asm = load(&opts)?;
+        let (outcome, gas_used) = run(&opts, &asm)?;
+        let output = match outcome {
+            UserOutcome::Success(output) => output,
+            outcome => eyre::bail!("keccak failed: {outcome}"),
+        };
+        assert_eq!(output, crypto::keccak(preimage));
+        assert!(gas_used > 0 && gas_used < opts.gas);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create() {
+        let mut evm = EvmApiRequestor::new(LocalEvm::default());
+        let (result, _, _) = evm.create1(vec![], Bytes32::default(), 0);
+        let error = result.unwrap_err().to_string();
+        assert_eq!(error, "no contracts to create locally");
+    }
+}
@@ -2,7 +2,7 @@
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
 use arbutil::Bytes32;
-use eyre::Result;
+use eyre::{eyre, Result};
 use lazy_static::lazy_static;
 use lru::LruCache;
 use parking_lot::Mutex;
@@ -20,6 +20,10 @@ macro_rules! cache {
     };
 }
 
+/// Caches deserialized modules so that repeated calls only pay for instantiation.
+///
+/// Items are keyed by module hash rather than address. Since the hash commits to the code,
+/// redeploying at the same address yields a new key and never observes a stale module.
 pub struct InitCache {
     long_term: HashMap<CacheKey, CacheItem>,
     lru: LruCache<CacheKey, CacheItem>,
@@ -42,6 +46,10 @@ impl CacheKey {
     }
 }
 
+/// A module paired with the engine that deserialized it.
+///
+/// Engines are deliberately not shared across modules: an engine owns the executable memory of
+/// everything it loads until the engine itself is dropped, so sharing one would leak evicted code.
 #[derive(Clone)]
 struct CacheItem {
     module: Module,
@@ -120,7 +128,10 @@ impl InitCache {
         }
         drop(cache);
 
-        let engine = CompileConfig::version(version, debug).engine();
+        let compile = CompileConfig::try_version(version, debug)
+            .ok_or_else(|| eyre!("unknown Stylus version {version}"))?;
+        let engine = compile.engine();
+        let module = crate::native::check_target(module)?;
         let module = unsafe { Module::deserialize_unchecked(&engine, module)? };
 
         let item = CacheItem::new(module, engine);
@@ -146,6 +157,11 @@ impl InitCache {
         }
     }
 
+    /// Empties the LRU cache, leaving the long term cache intact.
+    pub fn clear_lru_cache() {
+        cache!().lru.clear();
+    }
+
     pub fn clear_long_term(long_term_tag: u32) {
         if long_term_tag != Self::ARBOS_TAG {
             return;
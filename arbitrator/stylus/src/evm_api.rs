@@ -3,10 +3,11 @@
 
 use crate::{GoSliceData, RustSlice};
 use arbutil::evm::{
-    api::{EvmApiMethod, EVM_API_METHOD_REQ_OFFSET},
+    api::{DataReader, EvmApiMethod, EVM_API_METHOD_REQ_OFFSET},
     req::RequestHandler,
 };
 
+#[derive(Clone, Copy)]
 #[repr(C)]
 pub struct NativeRequestHandler {
     pub handle_request_fptr: unsafe extern "C" fn(
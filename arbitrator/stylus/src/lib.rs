@@ -13,9 +13,9 @@ use arbutil::{
 };
 use cache::InitCache;
 use evm_api::NativeRequestHandler;
-use eyre::ErrReport;
+use eyre::{ensure, eyre, ErrReport, Result};
 use native::NativeInstance;
-use prover::programs::{prelude::*, StylusData};
+use prover::programs::{config::PricingParams, prelude::*, StylusData};
 use run::RunProgram;
 use std::{marker::PhantomData, mem, ptr};
 
@@ -37,6 +37,9 @@ mod test;
 #[cfg(all(test, feature = "benchmark"))]
 mod benchmarks;
 
+/// Version of the FFI exposed to Go. Bump whenever a signature or `#[repr(C)]` layout changes.
+pub const STYLUS_ABI_VERSION: u32 = 1;
+
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct GoSliceData {
@@ -57,23 +60,62 @@ impl GoSliceData {
         }
     }
 
-    fn slice(&self) -> &[u8] {
+    /// Views the data as a slice, failing if Go passed a malformed pointer.
+    fn try_slice(&self) -> Result<&[u8]> {
         if self.len == 0 {
-            return &[];
+            return Ok(&[]);
         }
-        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+        ensure!(!self.ptr.is_null(), "null GoSliceData of len {}", self.len);
+        ensure!(
+            self.len <= isize::MAX as usize,
+            "GoSliceData too long: {}",
+            self.len
+        );
+        Ok(unsafe { std::slice::from_raw_parts(self.ptr, self.len) })
     }
 }
 
 impl DataReader for GoSliceData {
+    /// Views EVM responses, which Go constructs itself and so are always well-formed.
     fn slice(&self) -> &[u8] {
-        if self.len == 0 {
-            return &[];
-        }
-        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+        self.try_slice().expect("malformed EVM response")
     }
 }
 
+/// Unwraps the result of a fallible step of an FFI, or writes the error and returns `Failure`.
+macro_rules! ffi_try {
+    ($output:expr, $result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(err) => return $output.write_err(err),
+        }
+    };
+}
+
+/// Dereferences a pointer passed in by Go, or writes an error and returns `Failure` if null.
+macro_rules! ffi_deref {
+    ($output:expr, $ptr:ident, $method:ident) => {
+        ffi_try!(
+            $output,
+            $ptr.$method()
+                .ok_or_else(|| eyre!("null {} pointer", stringify!($ptr)))
+        )
+    };
+    ($output:expr, $ptr:ident) => {
+        ffi_deref!($output, $ptr, as_mut)
+    };
+}
+
+/// Dereferences the output, returning a bare `Failure` if Go passed a null pointer.
+macro_rules! ffi_output {
+    ($output:ident) => {
+        match $output.as_mut() {
+            Some(output) => output,
+            None => return UserOutcomeKind::Failure,
+        }
+    };
+}
+
 #[repr(C)]
 pub struct RustSlice<'a> {
     ptr: *const u8,
@@ -91,6 +133,11 @@ impl<'a> RustSlice<'a> {
     }
 }
 
+/// A vec whose ownership passes to Go, which frees it via `stylus_drop_vec`.
+///
+/// Go must zero-initialize these before handing them to an FFI, since writing frees whatever a
+/// previous write left behind. Every FFI returning a status leaves its output well-formed, either
+/// null or written, so dropping it afterward is always safe.
 #[repr(C)]
 pub struct RustBytes {
     ptr: *mut u8,
@@ -103,7 +150,11 @@ impl RustBytes {
         Vec::from_raw_parts(self.ptr, self.len, self.cap)
     }
 
+    /// Writes the vec, first freeing any allocation a previous write left behind.
     unsafe fn write(&mut self, mut vec: Vec<u8>) {
+        if !self.ptr.is_null() {
+            mem::drop(ptr::read(self).into_vec());
+        }
         self.ptr = vec.as_mut_ptr();
         self.len = vec.len();
         self.cap = vec.capacity();
@@ -122,6 +173,53 @@ impl RustBytes {
     }
 }
 
+/// Returns the version of the FFI this library was built with.
+#[no_mangle]
+pub extern "C" fn stylus_abi_version() -> u32 {
+    STYLUS_ABI_VERSION
+}
+
+/// Converts evm gas to ink exactly as `stylus_call` does, saturating on overflow.
+#[no_mangle]
+pub extern "C" fn stylus_gas_to_ink(pricing: PricingParams, gas: u64) -> u64 {
+    pricing.gas_to_ink(gas)
+}
+
+/// Converts ink to evm gas exactly as `stylus_call` does, rounding down.
+#[no_mangle]
+pub extern "C" fn stylus_ink_to_gas(pricing: PricingParams, ink: u64) -> u64 {
+    pricing.ink_to_gas(ink)
+}
+
+/// Describes the compilation parameters of a Stylus version, letting nodes detect versions
+/// newer than this binary supports.
+///
+/// On success, `output` holds the heap bound in pages, the max frame size in words, and the max
+/// frame contention, as big-endian `u32`s. Unknown versions produce a `Failure`.
+///
+/// # Safety
+///
+/// `output` must not be null.
+#[no_mangle]
+pub unsafe extern "C" fn stylus_config_info(
+    version: u16,
+    output: *mut RustBytes,
+) -> UserOutcomeKind {
+    util::abort_on_panic(|| {
+        let output = ffi_output!(output);
+        let Some(compile) = CompileConfig::try_version(version, false) else {
+            return output.write_err(eyre!("unknown Stylus version {version}"));
+        };
+        let bounds = compile.bounds;
+        let mut info = Vec::with_capacity(12);
+        info.extend(bounds.heap_bound.0.to_be_bytes());
+        info.extend(bounds.max_frame_size.to_be_bytes());
+        info.extend(u32::from(bounds.max_frame_contention).to_be_bytes());
+        output.write(info);
+        UserOutcomeKind::Success
+    })
+}
+
 /// Instruments and "activates" a user wasm.
 ///
 /// The `output` is either the serialized asm & module pair or an error string.
@@ -146,29 +244,111 @@ pub unsafe extern "C" fn stylus_activate(
     stylus_data: *mut StylusData,
     gas: *mut u64,
 ) -> UserOutcomeKind {
-    let wasm = wasm.slice();
-    let output = &mut *output;
-    let module_hash = &mut *module_hash;
-    let codehash = &*codehash;
-    let gas = &mut *gas;
-
-    let (asm, module, info) =
-        match native::activate(wasm, codehash, version, page_limit, debug, gas) {
-            Ok(val) => val,
-            Err(err) => return output.write_err(err),
+    util::abort_on_panic(|| {
+        let output = ffi_output!(output);
+        let wasm = ffi_try!(output, wasm.try_slice());
+        let asm_len = ffi_deref!(output, asm_len);
+        let codehash = ffi_deref!(output, codehash, as_ref);
+        let module_hash = ffi_deref!(output, module_hash);
+        let stylus_data = ffi_deref!(output, stylus_data);
+        let gas = ffi_deref!(output, gas);
+
+        let (asm, module, info) = ffi_try!(
+            output,
+            native::activate(wasm, codehash, version, page_limit, debug, gas)
+        );
+        *asm_len = asm.len();
+        *module_hash = module.hash();
+        *stylus_data = info;
+
+        let mut data = asm;
+        data.extend(&*module.into_bytes());
+        output.write(data);
+        UserOutcomeKind::Success
+    })
+}
+
+/// Compiles an activated user wasm for another machine, such as a validator of a different
+/// architecture. The `target` is a triple optionally followed by `+`-separated CPU features.
+///
+/// The wasm must pass the same checks as in `stylus_validate`. The `output` is either the
+/// serialized asm, which records its target, or an error string.
+///
+/// # Safety
+///
+/// `output` must not be null.
+#[no_mangle]
+pub unsafe extern "C" fn stylus_compile_for_target(
+    wasm: GoSliceData,
+    page_limit: u16,
+    version: u16,
+    debug: bool,
+    target: GoSliceData,
+    output: *mut RustBytes,
+) -> UserOutcomeKind {
+    util::abort_on_panic(|| {
+        let output = ffi_output!(output);
+        let wasm = ffi_try!(output, wasm.try_slice());
+        let target = ffi_try!(output, target.try_slice());
+        let Ok(target) = std::str::from_utf8(target) else {
+            return output.write_err(eyre!("target must be utf8"));
+        };
+        let Some(compile) = CompileConfig::try_version(version, debug) else {
+            return output.write_err(eyre!("unknown Stylus version {version}"));
         };
-    *asm_len = asm.len();
-    *module_hash = module.hash();
-    *stylus_data = info;
-
-    let mut data = asm;
-    data.extend(&*module.into_bytes());
-    output.write(data);
-    UserOutcomeKind::Success
+
+        let asm = ffi_try!(
+            output,
+            native::module_for_target(wasm, page_limit, compile, target)
+        );
+        output.write(asm);
+        UserOutcomeKind::Success
+    })
+}
+
+/// Checks whether a user wasm would pass activation's static checks, without compiling it.
+///
+/// Unlike `stylus_activate`, this charges no gas and produces no module, so the result
+/// carries no consensus meaning. The `output` holds an error string on failure.
+///
+/// # Safety
+///
+/// `output` and `stylus_data` must not be null.
+#[no_mangle]
+pub unsafe extern "C" fn stylus_validate(
+    wasm: GoSliceData,
+    page_limit: u16,
+    version: u16,
+    debug: bool,
+    output: *mut RustBytes,
+    stylus_data: *mut StylusData,
+) -> UserOutcomeKind {
+    util::abort_on_panic(|| {
+        let output = ffi_output!(output);
+        let wasm = ffi_try!(output, wasm.try_slice());
+        let stylus_data = ffi_deref!(output, stylus_data);
+
+        *stylus_data = ffi_try!(output, native::validate(wasm, version, page_limit, debug));
+        UserOutcomeKind::Success
+    })
 }
 
 /// Calls an activated user program.
 ///
+/// On `Success` and `Revert` the `output` holds exactly the program's return data.
+/// On `Failure` it holds a diagnostic error message instead, which callers shouldn't surface
+/// as return data. Other statuses produce no output.
+///
+/// The amount of gas left is written back via the `gas` pointer on every status.
+/// Only stack overflows burn the remaining gas; traps and other failures refund whatever ink
+/// the program didn't use, just as successful calls and reverts do.
+///
+/// # Panics
+///
+/// Panics rather than returning a status when the module can't be instantiated, saving the
+/// offending wasm to disk. Such a module passed activation, so continuing would risk diverging
+/// from the prover. The panic is caught at the FFI boundary, which then aborts the node.
+///
 /// # Safety
 ///
 /// `module` must represent a valid module produced from `stylus_activate`.
@@ -185,45 +365,154 @@ pub unsafe extern "C" fn stylus_call(
     gas: *mut u64,
     long_term_tag: u32,
 ) -> UserOutcomeKind {
-    let module = module.slice();
-    let calldata = calldata.slice().to_vec();
-    let evm_api = EvmApiRequestor::new(req_handler);
-    let pricing = config.pricing;
-    let output = &mut *output;
-    let ink = pricing.gas_to_ink(*gas);
-
-    // Safety: module came from compile_user_wasm and we've paid for memory expansion
-    let instance = unsafe {
-        NativeInstance::deserialize_cached(
-            module,
+    util::abort_on_panic(|| {
+        let output = ffi_output!(output);
+        let module = ffi_try!(output, module.try_slice());
+        let calldata = ffi_try!(output, calldata.try_slice()).to_vec();
+        let gas = ffi_deref!(output, gas);
+        if CompileConfig::try_version(config.version, debug_chain).is_none() {
+            let version = config.version;
+            return output.write_err(eyre!("unknown Stylus version {version}"));
+        }
+        let evm_api = EvmApiRequestor::new(req_handler);
+        let pricing = config.pricing;
+        let ink = pricing.gas_to_ink(*gas);
+
+        // Safety: module came from compile_user_wasm and we've paid for memory expansion
+        let instance = unsafe {
+            NativeInstance::deserialize_cached(
+                module,
+                config.version,
+                evm_api,
+                evm_data,
+                long_term_tag,
+                debug_chain,
+            )
+        };
+        let mut instance = match instance {
+            Ok(instance) => instance,
+            Err(error) => util::panic_with_wasm(module, error.wrap_err("init failed")),
+        };
+
+        let status = match instance.run_main(&calldata, config, ink) {
+            Err(e) | Ok(UserOutcome::Failure(e)) => output.write_err(e.wrap_err("call failed")),
+            Ok(outcome) => output.write_outcome(outcome),
+        };
+        let ink_left = match status.burns_all_gas() {
+            true => 0, // take all gas when out of stack
+            false => instance.ink_left().into(),
+        };
+        *gas = pricing.ink_to_gas(ink_left);
+        status
+    })
+}
+
+/// Times the activation and compilation of a user wasm, then `run_main` over a number of fresh
+/// instantiations of the result.
+///
+/// Activation is timed apart from compiling the asm, mirroring the two halves of
+/// `stylus_activate`. No gas is charged. Each iteration deserializes the asm directly, bypassing
+/// the `InitCache`, so call timings don't depend on what's been cached.
+///
+/// On success, `output` holds the activation and compile nanosecond timings, followed by the
+/// median and p99 call timings, each as a big-endian `u64`. Otherwise it holds the first error.
+///
+/// # Safety
+///
+/// `output` must not be null.
+#[cfg(feature = "benchmark")]
+#[no_mangle]
+pub unsafe extern "C" fn stylus_benchmark(
+    wasm: GoSliceData,
+    page_limit: u16,
+    calldata: GoSliceData,
+    config: StylusConfig,
+    req_handler: NativeRequestHandler,
+    evm_data: EvmData,
+    debug_chain: bool,
+    gas: u64,
+    iterations: u32,
+    output: *mut RustBytes,
+) -> UserOutcomeKind {
+    util::abort_on_panic(|| {
+        use prover::machine::Module as ProverModule;
+        use std::time::Instant;
+
+        let output = ffi_output!(output);
+        let wasm = ffi_try!(output, wasm.try_slice());
+        let calldata = ffi_try!(output, calldata.try_slice()).to_vec();
+        let Some(compile) = CompileConfig::try_version(config.version, debug_chain) else {
+            let version = config.version;
+            return output.write_err(eyre!("unknown Stylus version {version}"));
+        };
+        let ink = config.pricing.gas_to_ink(gas);
+
+        if iterations == 0 {
+            return output.write_err(eyre!("no iterations"));
+        }
+
+        let start = Instant::now();
+        let codehash = Bytes32::default(); // only affects the module hash
+        let mut activation_gas = u64::MAX;
+        let activation = ProverModule::activate(
+            wasm,
+            &codehash,
             config.version,
-            evm_api,
-            evm_data,
-            long_term_tag,
+            page_limit,
             debug_chain,
-        )
-    };
-    let mut instance = match instance {
-        Ok(instance) => instance,
-        Err(error) => util::panic_with_wasm(module, error.wrap_err("init failed")),
-    };
+            &mut activation_gas,
+        );
+        let activate_time = start.elapsed().as_nanos() as u64;
+        if let Err(error) = activation {
+            return output.write_err(error.wrap_err("activation failed"));
+        }
 
-    let status = match instance.run_main(&calldata, config, ink) {
-        Err(e) | Ok(UserOutcome::Failure(e)) => output.write_err(e.wrap_err("call failed")),
-        Ok(outcome) => output.write_outcome(outcome),
-    };
-    let ink_left = match status {
-        UserOutcomeKind::OutOfStack => 0, // take all gas when out of stack
-        _ => instance.ink_left().into(),
-    };
-    *gas = pricing.ink_to_gas(ink_left);
-    status
+        let start = Instant::now();
+        let module = native::module(wasm, compile.clone());
+        let compile_time = start.elapsed().as_nanos() as u64;
+        let module = ffi_try!(output, module);
+
+        let mut timings = Vec::with_capacity(iterations as usize);
+        for _ in 0..iterations {
+            let evm_api = EvmApiRequestor::new(req_handler);
+            let instance = NativeInstance::deserialize(&module, compile.clone(), evm_api, evm_data);
+            let mut instance = match instance {
+                Ok(instance) => instance,
+                Err(error) => return output.write_err(error.wrap_err("init failed")),
+            };
+
+            let start = Instant::now();
+            let outcome = instance.run_main(&calldata, config, ink);
+            timings.push(start.elapsed().as_nanos() as u64);
+
+            if let Err(error) = outcome {
+                return output.write_err(error.wrap_err("call failed"));
+            }
+        }
+        timings.sort_unstable();
+
+        let median = timings[timings.len() / 2];
+        let p99 = timings[(timings.len() * 99 / 100).min(timings.len() - 1)];
+        let timings = [activate_time, compile_time, median, p99];
+        output.write(timings.iter().flat_map(|x| x.to_be_bytes()).collect());
+        UserOutcomeKind::Success
+    })
 }
 
 /// resize lru
 #[no_mangle]
 pub extern "C" fn stylus_cache_lru_resize(size: u32) {
-    InitCache::set_lru_size(size);
+    util::abort_on_panic(|| {
+        InitCache::set_lru_size(size);
+    })
+}
+
+/// Empties the lru cache. Programs in the long term cache remain.
+#[no_mangle]
+pub extern "C" fn stylus_cache_lru_clear() {
+    util::abort_on_panic(|| {
+        InitCache::clear_lru_cache();
+    })
 }
 
 /// Caches an activated user program.
@@ -241,9 +530,12 @@ pub unsafe extern "C" fn stylus_cache_module(
     arbos_tag: u32,
     debug: bool,
 ) {
-    if let Err(error) = InitCache::insert(module_hash, module.slice(), version, arbos_tag, debug) {
-        panic!("tried to cache invalid asm!: {error}");
-    }
+    util::abort_on_panic(|| {
+        let module = module.try_slice().expect("malformed module"); // aborts
+        if let Err(error) = InitCache::insert(module_hash, module, version, arbos_tag, debug) {
+            panic!("tried to cache invalid asm!: {error}");
+        }
+    })
 }
 
 /// Evicts an activated user program from the init cache.
@@ -254,13 +546,17 @@ pub extern "C" fn stylus_evict_module(
     arbos_tag: u32,
     debug: bool,
 ) {
-    InitCache::evict(module_hash, version, arbos_tag, debug);
+    util::abort_on_panic(|| {
+        InitCache::evict(module_hash, version, arbos_tag, debug);
+    })
 }
 
 /// Reorgs the init cache. This will likely never happen.
 #[no_mangle]
 pub extern "C" fn stylus_reorg_vm(_block: u64, arbos_tag: u32) {
-    InitCache::clear_long_term(arbos_tag);
+    util::abort_on_panic(|| {
+        InitCache::clear_long_term(arbos_tag);
+    })
 }
 
 /// Frees the vector. Does nothing when the vector is null.
@@ -270,7 +566,9 @@ pub extern "C" fn stylus_reorg_vm(_block: u64, arbos_tag: u32) {
 /// Must only be called once per vec.
 #[no_mangle]
 pub unsafe extern "C" fn stylus_drop_vec(vec: RustBytes) {
-    if !vec.ptr.is_null() {
-        mem::drop(vec.into_vec())
-    }
+    util::abort_on_panic(|| {
+        if !vec.ptr.is_null() {
+            mem::drop(vec.into_vec())
+        }
+    })
 }
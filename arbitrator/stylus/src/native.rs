@@ -14,8 +14,9 @@ use arbutil::{
     operator::OperatorCode,
     Bytes32, Color,
 };
-use eyre::{bail, eyre, ErrReport, Result};
+use eyre::{bail, ensure, eyre, ErrReport, Result, WrapErr};
 use prover::{
+    binary::WasmBinary,
     machine::Module as ProverModule,
     programs::{
         config::PricingParams,
@@ -31,10 +32,11 @@ use std::{
     collections::BTreeMap,
     fmt::Debug,
     ops::{Deref, DerefMut},
+    str::FromStr,
 };
 use wasmer::{
-    imports, AsStoreMut, Function, FunctionEnv, Instance, Memory, Module, Pages, Store,
-    TypedFunction, Value, WasmTypeList,
+    imports, AsStoreMut, CpuFeature, Function, FunctionEnv, Instance, Memory, Module, Pages, Store,
+    Target, Triple, TypedFunction, Value, WasmTypeList,
 };
 use wasmer_vm::VMExtern;
 
@@ -99,12 +101,17 @@ impl<D: DataReader, E: EvmApi<D>> NativeInstance<D, E> {
     ) -> Result<Self> {
         let env = WasmEnv::new(compile, None, evm, evm_data);
         let store = env.compile.store();
+        let module = check_target(module)?;
         let module = unsafe { Module::deserialize_unchecked(&store, module)? };
         Self::from_module(module, store, env)
     }
 
     /// Creates a `NativeInstance` from a serialized module, or from a cached one if known.
     ///
+    /// Instances are single-use: a program's memory and globals persist across calls, so reusing
+    /// one would diverge from a fresh execution. The cache instead amortizes deserialization,
+    /// leaving only the comparatively cheap instantiation to be done per call.
+    ///
     /// # Safety
     ///
     /// `module` must represent a valid module.
@@ -116,7 +123,8 @@ impl<D: DataReader, E: EvmApi<D>> NativeInstance<D, E> {
         mut long_term_tag: u32,
         debug: bool,
     ) -> Result<Self> {
-        let compile = CompileConfig::version(version, debug);
+        let compile = CompileConfig::try_version(version, debug)
+            .ok_or_else(|| eyre!("unknown Stylus version {version}"))?;
         let env = WasmEnv::new(compile, None, evm, evm_data);
         let module_hash = env.evm_data.module_hash;
 
@@ -434,6 +442,94 @@ pub fn module(wasm: &[u8], compile: CompileConfig) -> Result<Vec<u8>> {
     Ok(module.to_vec())
 }
 
+/// Prefixes modules compiled for a foreign target, followed by the target's length and name.
+const TARGET_MAGIC: &[u8] = b"\0stylus-target\0";
+
+/// Parses a target triple optionally followed by `+`-separated CPU features.
+fn parse_target(target: &str) -> Result<Target> {
+    let mut parts = target.split('+');
+    let triple = parts.next().unwrap_or_default();
+    let triple = Triple::from_str(triple).map_err(|e| eyre!("bad target {triple}: {e}"))?;
+
+    let mut features = CpuFeature::set();
+    for flag in parts {
+        let feature =
+            CpuFeature::from_str(flag).map_err(|_| eyre!("unknown cpu feature {flag}"))?;
+        features.insert(feature);
+    }
+    Ok(Target::new(triple, features))
+}
+
+/// Compiles a user wasm for another machine, described by a target triple optionally followed by
+/// `+`-separated CPU features, e.g. `aarch64-unknown-linux-gnu+neon`.
+///
+/// The wasm is first put through activation's checks, since it can't be instantiated here.
+/// Foreign modules record their target, which hosts check before deserializing.
+pub fn module_for_target(
+    wasm: &[u8],
+    page_limit: u16,
+    compile: CompileConfig,
+    target: &str,
+) -> Result<Vec<u8>> {
+    let machine = parse_target(target)?;
+    check_user(wasm, page_limit, &compile)?;
+    if *machine.triple() == Triple::host() && target == machine.triple().to_string() {
+        return module(wasm, compile);
+    }
+
+    let engine = compile.engine_for_target(machine);
+    let store = Store::new(engine);
+    let module = Module::new(&store, wasm)?;
+
+    let mut asm = TARGET_MAGIC.to_vec();
+    asm.extend((target.len() as u32).to_be_bytes());
+    asm.extend(target.as_bytes());
+    asm.extend(&*module.serialize()?);
+    Ok(asm)
+}
+
+/// Strips the header of a module compiled by [`module_for_target`], failing if this machine
+/// doesn't match its target. Modules compiled for the host have no header.
+pub fn check_target(module: &[u8]) -> Result<&[u8]> {
+    let Some(data) = module.strip_prefix(TARGET_MAGIC) else {
+        return Ok(module);
+    };
+    ensure!(data.len() >= 4, "truncated target header");
+    let (len, data) = data.split_at(4);
+    let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+    ensure!(data.len() >= len, "truncated target header");
+    let (target, module) = data.split_at(len);
+
+    let target = std::str::from_utf8(target)?;
+    let machine = parse_target(target)?;
+    ensure!(
+        *machine.triple() == Triple::host()
+            && machine.cpu_features().is_subset(CpuFeature::for_host()),
+        "module compiled for {}",
+        target.red()
+    );
+    Ok(module)
+}
+
+/// Runs activation's static checks without paying for or compiling the wasm.
+pub fn validate(wasm: &[u8], version: u16, page_limit: u16, debug: bool) -> Result<StylusData> {
+    let Some(compile) = CompileConfig::try_version(version, debug) else {
+        bail!("unknown Stylus version {version}");
+    };
+    check_user(wasm, page_limit, &compile)
+}
+
+fn check_user(wasm: &[u8], page_limit: u16, compile: &CompileConfig) -> Result<StylusData> {
+    let codehash = Bytes32::default(); // only affects the module hash
+    let (bin, stylus_data) = WasmBinary::parse_user(wasm, page_limit, compile, &codehash)
+        .wrap_err("failed to parse wasm")?;
+
+    // checks imports the same way activation does, just without metering
+    ProverModule::from_user_binary(&bin, compile.debug.debug_funcs, Some(stylus_data))
+        .wrap_err("failed to build user module")?;
+    Ok(stylus_data)
+}
+
 pub fn activate(
     wasm: &[u8],
     codehash: &Bytes32,
@@ -442,7 +538,9 @@ pub fn activate(
     debug: bool,
     gas: &mut u64,
 ) -> Result<(Vec<u8>, ProverModule, StylusData)> {
-    let compile = CompileConfig::version(version, debug);
+    let Some(compile) = CompileConfig::try_version(version, debug) else {
+        bail!("unknown Stylus version {version}");
+    };
     let (module, stylus_data) =
         ProverModule::activate(wasm, codehash, version, page_limit, debug, gas)?;
 
@@ -11,11 +11,29 @@ use prover::machine::Machine;
 use prover::programs::{prelude::*, STYLUS_ENTRY_POINT};
 
 pub trait RunProgram {
-    fn run_main(&mut self, args: &[u8], config: StylusConfig, ink: u64) -> Result<UserOutcome>;
+    fn run_main(&mut self, args: &[u8], config: StylusConfig, ink: u64) -> Result<UserOutcome> {
+        self.run_export(STYLUS_ENTRY_POINT, args, config, ink)
+    }
+
+    /// Runs an export with the same `(args_len: u32) -> status: u32` shape as the entrypoint.
+    /// Only the entrypoint is ever called onchain; other exports are for tooling and tests.
+    fn run_export(
+        &mut self,
+        name: &str,
+        args: &[u8],
+        config: StylusConfig,
+        ink: u64,
+    ) -> Result<UserOutcome>;
 }
 
 impl RunProgram for Machine {
-    fn run_main(&mut self, args: &[u8], config: StylusConfig, ink: u64) -> Result<UserOutcome> {
+    fn run_export(
+        &mut self,
+        name: &str,
+        args: &[u8],
+        config: StylusConfig,
+        ink: u64,
+    ) -> Result<UserOutcome> {
         macro_rules! call {
             ($module:expr, $func:expr, $args:expr) => {
                 call!($module, $func, $args, |error| UserOutcome::Failure(error))
@@ -43,7 +61,7 @@ impl RunProgram for Machine {
         self.set_ink(ink);
         self.set_stack(config.max_depth);
 
-        let status: u32 = call!("user", STYLUS_ENTRY_POINT, vec![args_len], |error| {
+        let status: u32 = call!("user", name, vec![args_len], |error| {
             if self.stack_left() == 0 {
                 return UserOutcome::OutOfStack;
             }
@@ -65,7 +83,13 @@ impl RunProgram for Machine {
 }
 
 impl<D: DataReader, E: EvmApi<D>> RunProgram for NativeInstance<D, E> {
-    fn run_main(&mut self, args: &[u8], config: StylusConfig, ink: u64) -> Result<UserOutcome> {
+    fn run_export(
+        &mut self,
+        name: &str,
+        args: &[u8],
+        config: StylusConfig,
+        ink: u64,
+    ) -> Result<UserOutcome> {
         use UserOutcome::*;
 
         self.set_ink(ink);
@@ -80,11 +104,14 @@ impl<D: DataReader, E: EvmApi<D>> RunProgram for NativeInstance<D, E> {
         if env.evm_data.tracing {
             let args_len = args.len() as u32;
             env.evm_api
-                .capture_hostio(STYLUS_ENTRY_POINT, &args_len.to_be_bytes(), &[], ink, ink);
+                .capture_hostio(name, &args_len.to_be_bytes(), &[], ink, ink);
         }
 
         let exports = &self.instance.exports;
-        let main = exports.get_typed_function::<u32, u32>(store, STYLUS_ENTRY_POINT)?;
+        let main = match exports.get_typed_function::<u32, u32>(store, name) {
+            Ok(main) => main,
+            Err(error) => return Ok(Failure(eyre!(error).wrap_err("bad export"))),
+        };
         let status = match main.call(store, args.len() as u32) {
             Ok(status) => status,
             Err(outcome) => {
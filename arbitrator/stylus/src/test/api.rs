@@ -1,32 +1,53 @@
 // Copyright 2022, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
-use crate::{native, run::RunProgram};
+use crate::{
+    native::{self, NativeInstance},
+    run::RunProgram,
+};
 use arbutil::{
+    crypto,
     evm::{
-        api::{EvmApi, VecReader},
+        api::{EvmApi, EvmApiMethod, EvmApiStatus, VecReader},
+        req::{EvmApiRequestor, RequestHandler},
         user::UserOutcomeKind,
-        EvmData,
+        EvmData, COLD_ACCOUNT_GAS, COLD_SLOAD_GAS, WARM_SLOAD_GAS,
     },
     Bytes20, Bytes32,
 };
-use eyre::Result;
+use eyre::{bail, Result};
 use parking_lot::Mutex;
 use prover::programs::{memory::MemoryModel, prelude::*};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use super::TestInstance;
 
+/// An emitted log's data and topic count.
+type Log = (Vec<u8>, u32);
+
+/// An account's balance and code.
+type Account = (Bytes32, Vec<u8>);
+
 #[derive(Clone, Debug)]
 pub(crate) struct TestEvmApi {
     contracts: Arc<Mutex<HashMap<Bytes20, Vec<u8>>>>,
+    accounts: Arc<Mutex<HashMap<Bytes20, Account>>>,
     storage: Arc<Mutex<HashMap<Bytes20, HashMap<Bytes32, Bytes32>>>>,
+    warm: Arc<Mutex<HashSet<(Bytes20, Bytes32)>>>,
+    warm_accounts: Arc<Mutex<HashSet<Bytes20>>>,
     program: Bytes20,
     write_result: Arc<Mutex<Vec<u8>>>,
+    logs: Arc<Mutex<Vec<Log>>>,
+    hostios: Arc<Mutex<Vec<(String, u64, u64)>>>,
     compile: CompileConfig,
     configs: Arc<Mutex<HashMap<Bytes20, StylusConfig>>>,
     evm_data: EvmData,
     pages: Arc<Mutex<(u16, u16)>>,
+    read_only: bool,
+    write_blocked: bool,
 }
 
 impl TestEvmApi {
@@ -39,26 +60,66 @@ impl TestEvmApi {
 
         let api = TestEvmApi {
             contracts: Arc::new(Mutex::new(HashMap::new())),
+            accounts: Arc::new(Mutex::new(HashMap::new())),
             storage: Arc::new(Mutex::new(storage)),
+            warm: Arc::new(Mutex::new(HashSet::new())),
+            warm_accounts: Arc::new(Mutex::new(HashSet::new())),
             program,
             write_result: Arc::new(Mutex::new(vec![])),
+            logs: Arc::new(Mutex::new(vec![])),
+            hostios: Arc::new(Mutex::new(vec![])),
             compile,
             configs: Arc::new(Mutex::new(HashMap::new())),
             evm_data,
             pages: Arc::new(Mutex::new((0, 0))),
+            read_only: false,
+            write_blocked: false,
         };
         (api, evm_data)
     }
 
     pub fn deploy(&mut self, address: Bytes20, config: StylusConfig, name: &str) -> Result<()> {
         let file = format!("tests/{name}/target/wasm32-unknown-unknown/release/{name}.wasm");
-        let wasm = std::fs::read(file)?;
+        self.deploy_file(address, config, &file)
+    }
+
+    pub fn deploy_file(
+        &mut self,
+        address: Bytes20,
+        config: StylusConfig,
+        file: &str,
+    ) -> Result<()> {
+        let wat = std::fs::read(file)?;
+        let wasm = wasmer::wat2wasm(&wat)?;
         let module = native::module(&wasm, self.compile.clone())?;
         self.contracts.lock().insert(address, module);
         self.configs.lock().insert(address, config);
         Ok(())
     }
 
+    /// Gives an account the balance and code that the account hostios will see.
+    pub fn set_account(&mut self, address: Bytes20, balance: Bytes32, code: Vec<u8>) {
+        self.accounts.lock().insert(address, (balance, code));
+    }
+
+    /// The logs emitted so far, each with its topic count.
+    pub fn logs(&self) -> Vec<Log> {
+        self.logs.lock().clone()
+    }
+
+    /// The hostios captured so far when tracing, each with its starting and ending ink.
+    pub fn hostios(&self) -> Vec<(String, u64, u64)> {
+        self.hostios.lock().clone()
+    }
+
+    /// Charges for an account access, which is cold the first time per EIP-2929.
+    fn account_gas(&self, address: Bytes20) -> u64 {
+        match self.warm_accounts.lock().insert(address) {
+            true => COLD_ACCOUNT_GAS,
+            false => WARM_SLOAD_GAS,
+        }
+    }
+
     pub fn set_pages(&mut self, open: u16) {
         let mut pages = self.pages.lock();
         pages.0 = open;
@@ -71,17 +132,29 @@ impl EvmApi<VecReader> for TestEvmApi {
         let storage = &mut self.storage.lock();
         let storage = storage.get_mut(&self.program).unwrap();
         let value = storage.get(&key).cloned().unwrap_or_default();
-        (value, 2100) // pretend worst case
+        let cost = match self.warm.lock().insert((self.program, key)) {
+            true => COLD_SLOAD_GAS,
+            false => WARM_SLOAD_GAS,
+        };
+        (value, cost)
     }
 
     fn cache_bytes32(&mut self, key: Bytes32, value: Bytes32) -> u64 {
+        if self.read_only {
+            self.write_blocked = true;
+            return 0;
+        }
         let storage = &mut self.storage.lock();
         let storage = storage.get_mut(&self.program).unwrap();
         storage.insert(key, value);
+        self.warm.lock().insert((self.program, key));
         0
     }
 
     fn flush_storage_cache(&mut self, _clear: bool, _gas_left: u64) -> Result<u64> {
+        if self.write_blocked {
+            bail!("write protection");
+        }
         let storage = &mut self.storage.lock();
         let storage = storage.get_mut(&self.program).unwrap();
         Ok(22100 * storage.len() as u64) // pretend worst case
@@ -144,8 +217,9 @@ impl EvmApi<VecReader> for TestEvmApi {
         gas_left: u64,
         gas_req: u64,
     ) -> (u32, u64, UserOutcomeKind) {
-        println!("note: overriding static call with call");
-        self.contract_call(contract, calldata, gas_left, gas_req, Bytes32::default())
+        let mut api = self.clone();
+        api.read_only = true;
+        api.contract_call(contract, calldata, gas_left, gas_req, Bytes32::default())
     }
 
     fn create1(
@@ -171,20 +245,36 @@ impl EvmApi<VecReader> for TestEvmApi {
         VecReader::new(self.write_result.lock().clone())
     }
 
-    fn emit_log(&mut self, _data: Vec<u8>, _topics: u32) -> Result<()> {
-        Ok(()) // pretend a log was emitted
+    fn emit_log(&mut self, data: Vec<u8>, topics: u32) -> Result<()> {
+        if self.read_only {
+            bail!("write protection");
+        }
+        self.logs.lock().push((data, topics));
+        Ok(())
     }
 
-    fn account_balance(&mut self, _address: Bytes20) -> (Bytes32, u64) {
-        unimplemented!()
+    fn account_balance(&mut self, address: Bytes20) -> (Bytes32, u64) {
+        let accounts = self.accounts.lock();
+        let balance = accounts.get(&address).map(|x| x.0).unwrap_or_default();
+        (balance, self.account_gas(address))
     }
 
-    fn account_code(&mut self, _address: Bytes20, _gas_left: u64) -> (VecReader, u64) {
-        unimplemented!()
+    fn account_code(&mut self, address: Bytes20, _gas_left: u64) -> (VecReader, u64) {
+        let accounts = self.accounts.lock();
+        let code = accounts
+            .get(&address)
+            .map(|x| x.1.clone())
+            .unwrap_or_default();
+        (VecReader::new(code), self.account_gas(address))
     }
 
-    fn account_codehash(&mut self, _address: Bytes20) -> (Bytes32, u64) {
-        unimplemented!()
+    fn account_codehash(&mut self, address: Bytes20) -> (Bytes32, u64) {
+        let accounts = self.accounts.lock();
+        let hash = match accounts.get(&address) {
+            Some((_, code)) => crypto::keccak(code).into(),
+            None => Bytes32::default(), // nonexistent accounts have no codehash
+        };
+        (hash, self.account_gas(address))
     }
 
     fn add_pages(&mut self, new: u16) -> u64 {
@@ -199,12 +289,80 @@ impl EvmApi<VecReader> for TestEvmApi {
 
     fn capture_hostio(
         &mut self,
-        _name: &str,
+        name: &str,
         _args: &[u8],
         _outs: &[u8],
-        _start_ink: u64,
-        _end_ink: u64,
+        start_ink: u64,
+        end_ink: u64,
     ) {
-        unimplemented!()
+        let hostio = (name.to_owned(), start_ink, end_ink);
+        self.hostios.lock().push(hostio);
+    }
+}
+
+/// Answers the requests of an [`EvmApiRequestor`] the way Go does, recording each one.
+///
+/// Unless told otherwise, requests succeed at no cost, with lookups returning zeroed words.
+#[derive(Clone, Default)]
+pub(crate) struct TestRequestHandler {
+    requests: Arc<Mutex<RequestLog>>,
+    responses: Arc<Mutex<HashMap<u32, Response>>>,
+}
+
+/// Each request made, in order, by kind and data.
+type RequestLog = Vec<(EvmApiMethod, Vec<u8>)>;
+
+/// A canned answer to a kind of request: its result, raw data, and gas cost.
+type Response = (Vec<u8>, Vec<u8>, u64);
+
+pub(crate) type RequestorInstance =
+    NativeInstance<VecReader, EvmApiRequestor<VecReader, TestRequestHandler>>;
+
+impl TestRequestHandler {
+    /// Sets the result, raw data, and gas cost with which to answer a kind of request.
+    pub fn respond(&self, method: EvmApiMethod, result: Vec<u8>, raw_data: Vec<u8>, cost: u64) {
+        let response = (result, raw_data, cost);
+        self.responses.lock().insert(method as u32, response);
+    }
+
+    /// The data of each request of a given kind made so far.
+    pub fn requests(&self, method: EvmApiMethod) -> Vec<Vec<u8>> {
+        let requests = self.requests.lock();
+        let requests = requests.iter().filter(|x| x.0 as u32 == method as u32);
+        requests.map(|x| x.1.clone()).collect()
+    }
+
+    /// Instantiates a program whose requests this handler answers.
+    pub fn instance(
+        &self,
+        path: &str,
+        evm_data: EvmData,
+        compile: &CompileConfig,
+        config: StylusConfig,
+    ) -> Result<RequestorInstance> {
+        let evm_api = EvmApiRequestor::new(self.clone());
+        NativeInstance::from_path(path, evm_api, evm_data, compile, config)
+    }
+}
+
+impl RequestHandler<VecReader> for TestRequestHandler {
+    fn request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> (Vec<u8>, VecReader, u64) {
+        use EvmApiMethod::*;
+        let req_data = req_data.as_ref().to_vec();
+        self.requests.lock().push((req_type, req_data));
+
+        if let Some((result, raw_data, cost)) = self.responses.lock().get(&(req_type as u32)) {
+            return (result.clone(), VecReader::new(raw_data.clone()), *cost);
+        }
+        let result = match req_type {
+            GetBytes32 | GetTransientBytes32 | AccountBalance | AccountCodeHash => vec![0; 32],
+            EmitLog | AccountCode | AddPages | CaptureHostIO => vec![],
+            _ => vec![EvmApiStatus::Success.into()],
+        };
+        (result, VecReader::new(vec![]), 0)
     }
 }
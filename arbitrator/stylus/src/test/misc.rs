@@ -3,12 +3,24 @@
 
 use super::test_configs;
 use crate::{
+    cache::InitCache,
     env::{Escape, MaybeEscape},
-    native::NativeInstance,
-    test::{check_instrumentation, new_test_machine},
+    native::{self, NativeInstance},
+    stylus_activate, stylus_config_info, stylus_drop_vec, stylus_gas_to_ink, stylus_ink_to_gas,
+    stylus_validate,
+    test::{
+        check_instrumentation, empty_bytes, ffi_call, go_slice, new_test_machine, random_bytes32,
+    },
+    GoSliceData, RustBytes,
 };
-use eyre::Result;
-use prover::programs::{prelude::*, start::StartMover};
+use arbutil::{
+    evm::{user::UserOutcomeKind, EvmData},
+    Bytes32,
+};
+use brotli::Dictionary;
+use eyre::{bail, ensure, Result};
+use prover::programs::{config::PricingParams, prelude::*, start::StartMover, StylusData};
+use std::{mem::MaybeUninit, ptr};
 use wasmer::{imports, Function};
 
 #[test]
@@ -80,3 +92,569 @@ fn test_console() -> Result<()> {
     machine.call_user_func(StartMover::NAME, vec![], ink)?;
     check_instrumentation(native, machine)
 }
+
+/// The results of activating a wasm through the FFI.
+struct Activation {
+    status: UserOutcomeKind,
+    output: Vec<u8>,
+    asm_len: usize,
+    module_hash: Bytes32,
+    info: StylusData,
+    gas: u64,
+}
+
+/// Activates a wasm through `stylus_activate`, just as Go does.
+fn activate(wasm: &[u8], version: u16, gas: u64) -> Activation {
+    let mut output = empty_bytes();
+    let mut asm_len = 0;
+    let codehash = Bytes32::default();
+    let mut module_hash = Bytes32::default();
+    let mut info: StylusData = unsafe { std::mem::zeroed() };
+    let mut gas = gas;
+
+    let status = unsafe {
+        stylus_activate(
+            go_slice(wasm),
+            128,
+            version,
+            true,
+            &mut output,
+            &mut asm_len,
+            &codehash,
+            &mut module_hash,
+            &mut info,
+            &mut gas,
+        )
+    };
+    let output = unsafe { output.into_vec() };
+    Activation {
+        status,
+        output,
+        asm_len,
+        module_hash,
+        info,
+        gas,
+    }
+}
+
+#[test]
+fn test_failure_output() -> Result<()> {
+    // a failure's output is just the error's text, led by the step that failed
+    let activation = activate(&[0xff; 32], 1, u64::MAX);
+    assert_eq!(activation.status, UserOutcomeKind::Failure);
+    let error = String::from_utf8(activation.output)?;
+    ensure!(
+        error.starts_with("failed to parse wasm"),
+        "wrong error: {error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_output_reuse() -> Result<()> {
+    let activate_into = |output: &mut RustBytes, wasm: &[u8]| unsafe {
+        let mut asm_len = 0;
+        let mut module_hash = Bytes32::default();
+        let mut info: StylusData = std::mem::zeroed();
+        let mut gas = u64::MAX;
+        stylus_activate(
+            go_slice(wasm),
+            128,
+            1,
+            true,
+            output,
+            &mut asm_len,
+            &Bytes32::default(),
+            &mut module_hash,
+            &mut info,
+            &mut gas,
+        )
+    };
+
+    // writing to an output frees whatever Rust left there before
+    let mut output = empty_bytes();
+    let wat = std::fs::read("tests/memory.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    assert_eq!(
+        activate_into(&mut output, &[0xff; 32]),
+        UserOutcomeKind::Failure
+    );
+    assert_eq!(activate_into(&mut output, &wasm), UserOutcomeKind::Success);
+    ensure!(output.len > 32, "output wasn't replaced");
+
+    // and Go hands each output back exactly once, null or not
+    unsafe {
+        stylus_drop_vec(output);
+        stylus_drop_vec(empty_bytes());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_activation_data() -> Result<()> {
+    let wat = std::fs::read("tests/memory.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let activation = activate(&wasm, 1, u64::MAX);
+    assert_eq!(activation.status, UserOutcomeKind::Success);
+
+    // the output is the asm followed by the prover's module, whose hash is written back
+    let asm_len = activation.asm_len;
+    ensure!(
+        asm_len > 0 && asm_len < activation.output.len(),
+        "bad asm len: {asm_len}"
+    );
+    assert_ne!(activation.module_hash, Bytes32::default());
+
+    // the metadata matches what validation reports without compiling
+    let info = activation.info;
+    let valid = native::validate(&wasm, 1, 128, true)?;
+    assert_eq!(info.footprint, 1);
+    assert_eq!(info.footprint, valid.footprint);
+    assert_eq!(info.init_cost, valid.init_cost);
+    assert_eq!(info.cached_init_cost, valid.cached_init_cost);
+    assert_eq!(info.asm_estimate, valid.asm_estimate);
+    assert_eq!(info.user_main, valid.user_main);
+    Ok(())
+}
+
+#[test]
+fn test_activation_gas() -> Result<()> {
+    // in bad-import5.wat
+    //     the memory is paid for before finding an import with the wrong signature
+
+    let wat = std::fs::read("tests/bad-mods/bad-import5.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let gas = 1_000_000;
+
+    // modules that fail validation fail activation, keeping the gas spent until then
+    let activation = activate(&wasm, 1, gas);
+    let error = String::from_utf8(activation.output)?;
+    assert_eq!(activation.status, UserOutcomeKind::Failure);
+    ensure!(
+        error.contains("failed to build user module"),
+        "wrong error: {error}"
+    );
+    ensure!(activation.gas < gas, "activation was free");
+
+    // running out of gas partway consumes it all
+    let activation = activate(&wasm, 1, 1000);
+    let error = String::from_utf8(activation.output)?;
+    ensure!(error.contains("out of gas"), "wrong error: {error}");
+    assert_eq!(activation.gas, 0);
+    Ok(())
+}
+
+#[test]
+fn test_footprint() -> Result<()> {
+    let entrypoint = r#"(func (export "user_entrypoint") (param i32) (result i32) i32.const 0)"#;
+    let wasm = |pages: u16| {
+        let wat = format!(r#"(module (memory (export "memory") {pages}) {entrypoint})"#);
+        wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+    };
+
+    // the footprint is the memory's initial size, which the heap bound and page limit cap
+    for pages in [0, 1, 3, 128] {
+        let activation = activate(&wasm(pages), 1, u64::MAX);
+        assert_eq!(activation.status, UserOutcomeKind::Success);
+        assert_eq!(activation.info.footprint, pages);
+    }
+    let activation = activate(&wasm(129), 1, u64::MAX);
+    let error = String::from_utf8(activation.output)?;
+    ensure!(error.contains("exceeds"), "wrong error: {error}");
+    Ok(())
+}
+
+#[test]
+fn test_persisted_asm() -> Result<()> {
+    // in counter.wat
+    //     a global and a word of memory count the calls made to the instance
+
+    // Go stores activated asm in the wasm database, later loading it into fresh processes
+    let wat = std::fs::read("tests/counter.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let activation = activate(&wasm, 1, u64::MAX);
+    assert_eq!(activation.status, UserOutcomeKind::Success);
+    let asm = &activation.output[..activation.asm_len];
+
+    let path = std::env::temp_dir().join(format!("stylus-asm-{}.bin", activation.module_hash));
+    std::fs::write(&path, asm)?;
+    let stored = std::fs::read(&path)?;
+    std::fs::remove_file(&path)?;
+
+    let (_, mut config, _) = test_configs();
+    config.version = 1;
+    let (status, output, _) = ffi_call(&stored, &[], config, EvmData::default(), 1_000_000);
+    assert_eq!(status, UserOutcomeKind::Success);
+    assert_eq!(output, [1, 0, 0, 0, 1, 0, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn test_module_stats() -> Result<()> {
+    let entrypoint = r#"(func (export "user_entrypoint") (param i32) (result i32) i32.const 0)"#;
+    let wasm = |data_len: usize| {
+        let data = "a".repeat(data_len);
+        let wat = format!(
+            r#"(module (memory (export "memory") 1 1) (data (i32.const 0) "{data}") {entrypoint})"#
+        );
+        wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+    };
+
+    // the stats are a pure function of the module
+    let empty = activate(&wasm(0), 1, u64::MAX);
+    let again = activate(&wasm(0), 1, u64::MAX);
+    assert_eq!(empty.status, UserOutcomeKind::Success);
+    assert_eq!(empty.module_hash, again.module_hash);
+    assert_eq!(empty.info.init_cost, again.info.init_cost);
+    assert_eq!(empty.info.cached_init_cost, again.info.cached_init_cost);
+    assert_eq!(empty.info.asm_estimate, again.info.asm_estimate);
+
+    // and data makes a program costlier to instantiate
+    let full = activate(&wasm(5000), 1, u64::MAX);
+    assert_eq!(full.status, UserOutcomeKind::Success);
+    let cached_diff = u64::from(full.info.cached_init_cost - empty.info.cached_init_cost);
+    ensure!(cached_diff >= 5000 * 75244 / 100_000, "data is too cheap");
+    ensure!(
+        full.info.init_cost > empty.info.init_cost,
+        "init is too cheap"
+    );
+    ensure!(
+        full.info.asm_estimate > empty.info.asm_estimate,
+        "bad estimate"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_init_costs() -> Result<()> {
+    let entrypoint = r#"(func (export "user_entrypoint") (param i32) (result i32) i32.const 0)"#;
+    let wasm = |funcs: usize| {
+        let funcs = "(func)".repeat(funcs);
+        let wat = format!(r#"(module (memory (export "memory") 1 1) {entrypoint} {funcs})"#);
+        wasmer::wat2wasm(wat.as_bytes()).unwrap().to_vec()
+    };
+
+    // version 1 charges the cached cost as part of the full one
+    let small = activate(&wasm(0), 1, u64::MAX).info;
+    let large = activate(&wasm(100), 1, u64::MAX).info;
+    ensure!(
+        small.cached_init_cost <= small.init_cost,
+        "cached costs more"
+    );
+    ensure!(
+        large.cached_init_cost <= large.init_cost,
+        "cached costs more"
+    );
+
+    // and both scale with the number of functions to instantiate
+    ensure!(
+        u64::from(large.init_cost - small.init_cost) >= 100 * 8252 / 1000,
+        "functions are too cheap"
+    );
+    ensure!(
+        u64::from(large.cached_init_cost - small.cached_init_cost) >= 100 * 13420 / 100_000,
+        "functions are too cheap"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_max_size_activation() -> Result<()> {
+    // Go bounds inflated programs by MaxWasmSize, which is 128 KiB
+    let data = "a".repeat(120 * 1024);
+    let entrypoint = r#"(func (export "user_entrypoint") (param i32) (result i32) i32.const 0)"#;
+    let wat = format!(
+        r#"(module (memory (export "memory") 2 2) (data (i32.const 0) "{data}") {entrypoint})"#
+    );
+    let wasm = wasmer::wat2wasm(wat.as_bytes())?;
+    ensure!(
+        wasm.len() > 120 * 1024 && wasm.len() <= 128 * 1024,
+        "wrong size"
+    );
+
+    // programs that large activate in one pass, the same way every time
+    let first = activate(&wasm, 1, u64::MAX);
+    let second = activate(&wasm, 1, u64::MAX);
+    assert_eq!(first.status, UserOutcomeKind::Success);
+    assert_eq!(first.module_hash, second.module_hash);
+    assert_eq!(first.asm_len, second.asm_len);
+    assert_eq!(first.gas, second.gas);
+    Ok(())
+}
+
+#[test]
+fn test_compressed_activation() -> Result<()> {
+    // Go inflates programs with the Stylus dictionary before passing them to stylus_activate
+    let wat = std::fs::read("tests/memory.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let dict = Dictionary::StylusProgram;
+    let deflate = brotli::compress(&wasm, 11, 22, dict).unwrap();
+    let inflate = brotli::decompress(&deflate, dict).unwrap();
+    assert_eq!(hex::encode(&inflate), hex::encode(&wasm));
+
+    // the inflated program activates exactly as the original does
+    let direct = activate(&wasm, 1, u64::MAX);
+    let inflated = activate(&inflate, 1, u64::MAX);
+    assert_eq!(direct.status, UserOutcomeKind::Success);
+    assert_eq!(inflated.status, UserOutcomeKind::Success);
+    assert_eq!(direct.module_hash, inflated.module_hash);
+    assert_eq!(direct.asm_len, inflated.asm_len);
+    assert_eq!(direct.gas, inflated.gas);
+
+    // and inflating into a fixed buffer bounds the size, which is how Go guards against zip bombs
+    let mut buffer = vec![MaybeUninit::uninit(); wasm.len() - 1];
+    assert!(brotli::decompress_fixed(&deflate, &mut buffer, dict).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_init_cache() -> Result<()> {
+    let wat = std::fs::read("tests/exit-early/exit-early.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = native::module(&wasm, CompileConfig::version(1, true))?;
+
+    // hammer the global cache from several threads at once
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let module = module.clone();
+            std::thread::spawn(move || -> Result<()> {
+                for _ in 0..16 {
+                    let hash = random_bytes32();
+                    InitCache::insert(hash, &module, 1, 1, true)?;
+                    ensure!(InitCache::get(hash, 1, true).is_some(), "missing module");
+
+                    // modules are never shared across versions or debug modes
+                    ensure!(InitCache::get(hash, 2, true).is_none(), "wrong version");
+                    ensure!(InitCache::get(hash, 1, false).is_none(), "wrong debug mode");
+
+                    // evicting from the long term cache demotes to the LRU
+                    InitCache::evict(hash, 1, 1, true);
+                    ensure!(
+                        InitCache::get(hash, 1, true).is_some(),
+                        "evicted module lost"
+                    );
+                }
+                Ok(())
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_abort_on_panic() {
+    // panics must never unwind into Go, so the FFIs abort instead
+    if std::env::var_os("STYLUS_TEST_PANIC").is_some() {
+        crate::util::abort_on_panic(|| panic!("deliberate panic"));
+    }
+    assert_eq!(crate::util::abort_on_panic(|| 7), 7);
+    assert_aborts("test::misc::test_abort_on_panic", "STYLUS_TEST_PANIC");
+}
+
+#[test]
+fn test_call_bad_module() {
+    // modules that fail to instantiate passed activation, so the node aborts rather than diverge
+    if std::env::var_os("STYLUS_TEST_BAD_MODULE").is_some() {
+        let (_, config, _) = test_configs();
+        ffi_call(&[0xff; 64], &[], config, EvmData::default(), 1_000_000);
+        unreachable!("called a bad module");
+    }
+    assert_aborts("test::misc::test_call_bad_module", "STYLUS_TEST_BAD_MODULE");
+}
+
+#[test]
+fn test_call_foreign_module() {
+    // asm for another machine is never produced locally, so loading it is a node fault
+    if std::env::var_os("STYLUS_TEST_FOREIGN_MODULE").is_some() {
+        let (_, config, _) = test_configs();
+        let wat = std::fs::read("tests/memory.wat").unwrap();
+        let wasm = wasmer::wat2wasm(&wat).unwrap();
+        let compile = CompileConfig::version(config.version, true);
+        let foreign = match cfg!(target_arch = "x86_64") {
+            true => "aarch64-unknown-linux-gnu",
+            false => "x86_64-unknown-linux-gnu+sse4.2",
+        };
+        let module = native::module_for_target(&wasm, 128, compile, foreign).unwrap();
+        ffi_call(&module, &[], config, EvmData::default(), 1_000_000);
+        unreachable!("called a foreign module");
+    }
+    assert_aborts(
+        "test::misc::test_call_foreign_module",
+        "STYLUS_TEST_FOREIGN_MODULE",
+    );
+}
+
+/// Reruns a test in a subprocess with the given env var set, checking that the process aborts.
+fn assert_aborts(test: &str, var: &str) {
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", test, "--nocapture"])
+        .env(var, "1")
+        .output()
+        .unwrap()
+        .status;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(status.signal(), Some(libc::SIGABRT));
+    }
+    assert!(!status.success());
+}
+
+#[test]
+fn test_go_slice() -> Result<()> {
+    let empty = GoSliceData::null();
+    assert!(empty.try_slice()?.is_empty());
+
+    let data = [1, 2, 3];
+    let slice = GoSliceData {
+        ptr: data.as_ptr(),
+        len: data.len(),
+    };
+    assert_eq!(slice.try_slice()?, data);
+    Ok(())
+}
+
+#[test]
+fn test_go_slice_malformed() {
+    // malformed inputs fail cleanly rather than panicking across the FFI
+    let data = [0_u8; 8];
+    let null = GoSliceData {
+        ptr: ptr::null(),
+        len: 32,
+    };
+    let oversized = GoSliceData {
+        ptr: data.as_ptr(),
+        len: usize::MAX,
+    };
+    let mut asm_len = 0;
+    let codehash = Bytes32::default();
+    let mut module_hash = Bytes32::default();
+    let mut info: StylusData = unsafe { std::mem::zeroed() };
+    let mut gas = u64::MAX;
+
+    macro_rules! activate {
+        ($wasm:expr, $output:expr, $info:expr) => {
+            unsafe {
+                stylus_activate(
+                    $wasm,
+                    128,
+                    1,
+                    true,
+                    $output,
+                    &mut asm_len,
+                    &codehash,
+                    &mut module_hash,
+                    $info,
+                    &mut gas,
+                )
+            }
+        };
+    }
+
+    for wasm in [null, oversized] {
+        let mut output = empty_bytes();
+        let status = activate!(wasm, &mut output, &mut info);
+        assert_eq!(status, UserOutcomeKind::Failure);
+        let error = String::from_utf8(unsafe { output.into_vec() }).unwrap();
+        assert!(error.contains("GoSliceData"), "wrong error: {error}");
+    }
+
+    // as do null outputs and out-params
+    let status = activate!(go_slice(&data), ptr::null_mut(), &mut info);
+    assert_eq!(status, UserOutcomeKind::Failure);
+
+    let mut output = empty_bytes();
+    let status = activate!(go_slice(&data), &mut output, ptr::null_mut());
+    assert_eq!(status, UserOutcomeKind::Failure);
+    let error = String::from_utf8(unsafe { output.into_vec() }).unwrap();
+    assert!(
+        error.contains("null stylus_data pointer"),
+        "wrong error: {error}"
+    );
+}
+
+#[test]
+fn test_rust_bytes_rewrite() {
+    let mut bytes = RustBytes {
+        ptr: ptr::null_mut(),
+        len: 0,
+        cap: 0,
+    };
+    unsafe {
+        bytes.write(vec![1; 64]);
+        bytes.write(vec![2; 32]); // frees the first write
+        assert_eq!(bytes.into_vec(), vec![2; 32]);
+    }
+}
+
+#[test]
+fn test_gas_conversion() {
+    let pricing = PricingParams::new(10_000);
+    assert_eq!(stylus_gas_to_ink(pricing, 7), 70_000);
+    assert_eq!(stylus_ink_to_gas(pricing, 79_999), 7); // partial gas rounds down
+    assert_eq!(stylus_gas_to_ink(pricing, u64::MAX), u64::MAX);
+    assert_eq!(stylus_ink_to_gas(pricing, u64::MAX), u64::MAX / 10_000);
+}
+
+#[test]
+fn test_config_info() {
+    let mut output = RustBytes {
+        ptr: ptr::null_mut(),
+        len: 0,
+        cap: 0,
+    };
+    unsafe {
+        assert_eq!(stylus_config_info(1, &mut output), UserOutcomeKind::Success);
+        let info = output.into_vec();
+        let word = |i: usize| u32::from_be_bytes(info[4 * i..4 * i + 4].try_into().unwrap());
+        assert_eq!((word(0), word(1), word(2)), (128, 10 * 1024, 4096));
+    }
+    assert!(CompileConfig::try_version(u16::MAX, false).is_none());
+}
+
+#[test]
+fn test_unknown_version() -> Result<()> {
+    let wat = std::fs::read("tests/memory.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let activation = activate(&wasm, u16::MAX, u64::MAX);
+    let error = String::from_utf8(activation.output)?;
+    assert_eq!(activation.status, UserOutcomeKind::Failure);
+    ensure!(
+        error.contains("unknown Stylus version"),
+        "wrong error: {error}"
+    );
+
+    let mut output = empty_bytes();
+    let mut info: StylusData = unsafe { std::mem::zeroed() };
+    let status =
+        unsafe { stylus_validate(go_slice(&wasm), 128, u16::MAX, true, &mut output, &mut info) };
+    let error = String::from_utf8(unsafe { output.into_vec() })?;
+    assert_eq!(status, UserOutcomeKind::Failure);
+    ensure!(
+        error.contains("unknown Stylus version"),
+        "wrong error: {error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_cache_unknown_version() -> Result<()> {
+    let wat = std::fs::read("tests/memory.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = native::module(&wasm, CompileConfig::version(1, true))?;
+
+    // caching asm under a version this binary does not know must fail rather than panic
+    let Err(error) = InitCache::insert(random_bytes32(), &module, u16::MAX, 1, true) else {
+        bail!("cached a module of an unknown version");
+    };
+    ensure!(
+        error.to_string().contains("unknown Stylus version"),
+        "wrong error: {error}"
+    );
+    Ok(())
+}
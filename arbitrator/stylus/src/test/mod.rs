@@ -1,15 +1,23 @@
 // Copyright 2022-2023, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
-use crate::{env::WasmEnv, native::NativeInstance, run::RunProgram, test::api::TestEvmApi};
+use crate::{
+    env::WasmEnv, evm_api::NativeRequestHandler, native::NativeInstance, run::RunProgram,
+    stylus_call, test::api::TestEvmApi, GoSliceData, RustBytes, RustSlice,
+};
 use arbutil::{
-    evm::{api::VecReader, user::UserOutcome},
+    crypto,
+    evm::{
+        api::VecReader,
+        user::{UserOutcome, UserOutcomeKind},
+        EvmData,
+    },
     Bytes20, Bytes32, Color,
 };
-use eyre::{bail, Result};
+use eyre::{bail, ensure, Result};
 use prover::{
     machine::GlobalState,
-    programs::{config::SigMap, prelude::*},
+    programs::{config::SigMap, prelude::*, STYLUS_ENTRY_POINT},
     Machine,
 };
 use rand::prelude::*;
@@ -116,6 +124,71 @@ fn random_bytes32() -> Bytes32 {
     data.into()
 }
 
+/// Borrows data the way Go passes it to the FFIs.
+fn go_slice(data: &[u8]) -> GoSliceData {
+    GoSliceData {
+        ptr: data.as_ptr(),
+        len: data.len(),
+    }
+}
+
+/// A zeroed output, as Go passes to the FFIs.
+fn empty_bytes() -> RustBytes {
+    RustBytes {
+        ptr: std::ptr::null_mut(),
+        len: 0,
+        cap: 0,
+    }
+}
+
+/// Answers every EVM request with an empty response at no cost.
+unsafe extern "C" fn no_requests(
+    _id: usize,
+    _req_type: u32,
+    _data: *mut RustSlice,
+    gas_cost: *mut u64,
+    result: *mut GoSliceData,
+    raw_data: *mut GoSliceData,
+) {
+    *gas_cost = 0;
+    *result = GoSliceData::null();
+    *raw_data = GoSliceData::null();
+}
+
+/// Calls a module through `stylus_call` as Go does, returning the status, output, and gas left.
+/// The program must not make any EVM requests.
+fn ffi_call(
+    module: &[u8],
+    args: &[u8],
+    config: StylusConfig,
+    mut evm_data: EvmData,
+    mut gas: u64,
+) -> (UserOutcomeKind, Vec<u8>, u64) {
+    // modules are cached by hash, so tests mustn't share one
+    evm_data.module_hash = crypto::keccak(module).into();
+
+    let handler = NativeRequestHandler {
+        handle_request_fptr: no_requests,
+        id: 0,
+    };
+    let mut output = empty_bytes();
+    let status = unsafe {
+        stylus_call(
+            go_slice(module),
+            go_slice(args),
+            config,
+            handler,
+            evm_data,
+            true,
+            &mut output,
+            &mut gas,
+            0,
+        )
+    };
+    let output = unsafe { output.into_vec() };
+    (status, output, gas)
+}
+
 fn test_compile_config() -> CompileConfig {
     let mut compile_config = CompileConfig::version(0, true);
     compile_config.debug.count_ops = true;
@@ -184,6 +257,56 @@ fn run_machine(
     }
 }
 
+/// Runs a program both natively and in the prover, failing if they disagree on the outcome,
+/// the data returned, or the ink left. Failures are compared by kind alone since the native and
+/// prover error messages differ.
+fn run_both(
+    path: &str,
+    args: &[u8],
+    compile: &CompileConfig,
+    config: StylusConfig,
+    ink: u64,
+) -> Result<UserOutcome> {
+    run_both_export(path, STYLUS_ENTRY_POINT, args, compile, config, ink)
+}
+
+/// Like [`run_both`], but calls the given export instead of the entrypoint.
+fn run_both_export(
+    path: &str,
+    name: &str,
+    args: &[u8],
+    compile: &CompileConfig,
+    config: StylusConfig,
+    ink: u64,
+) -> Result<UserOutcome> {
+    let mut native = TestInstance::new_linked(path, compile, config)?;
+    let mut machine = Machine::from_user_path(Path::new(path), compile)?;
+    let native_outcome = native.run_export(name, args, config, ink)?;
+    let machine_outcome = machine.run_export(name, args, config, ink)?;
+
+    let native_ink = native.ink_left();
+    let machine_ink = machine.ink_left();
+    let diverged = |what| {
+        format!(
+            "{path} diverged on {what} in {name}\n  native:  {native_outcome} with {native_ink:?}\n  machine: {machine_outcome} with {machine_ink:?}"
+        )
+    };
+    ensure!(
+        native_outcome.kind() == machine_outcome.kind(),
+        diverged("outcome")
+    );
+    ensure!(native_ink == machine_ink, diverged("ink"));
+
+    if native_outcome.kind() != UserOutcomeKind::Failure {
+        let native_data = format!("{native_outcome}");
+        ensure!(
+            native_data == format!("{machine_outcome}"),
+            diverged("data")
+        );
+    }
+    Ok(native_outcome)
+}
+
 fn check_instrumentation(mut native: TestInstance, mut machine: Machine) -> Result<()> {
     assert_eq!(native.ink_left(), machine.ink_left());
     assert_eq!(native.stack_left(), machine.stack_left());
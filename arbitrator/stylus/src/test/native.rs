@@ -8,18 +8,22 @@
 
 use crate::{
     run::RunProgram,
+    stylus_validate,
     test::{
-        check_instrumentation, random_bytes20, random_bytes32, random_ink, run_machine, run_native,
-        test_compile_config, test_configs, TestInstance,
+        api::{TestEvmApi, TestRequestHandler},
+        check_instrumentation, ffi_call, random_bytes20, random_bytes32, random_ink, run_both,
+        run_both_export, run_machine, run_native, test_compile_config, test_configs, TestInstance,
     },
+    GoSliceData, RustBytes,
 };
 use arbutil::{
     crypto,
     evm::{
-        api::EvmApi,
+        api::{DataReader, EvmApi, EvmApiMethod, EvmApiStatus},
         user::{UserOutcome, UserOutcomeKind},
+        EvmData, COLD_ACCOUNT_GAS, COLD_SLOAD_GAS, WARM_SLOAD_GAS,
     },
-    format, Bytes20, Bytes32, Color,
+    format, pricing, Bytes20, Bytes32, Color,
 };
 use eyre::{bail, ensure, Result};
 use prover::{
@@ -32,6 +36,7 @@ use prover::{
     },
     Machine,
 };
+use rand::Rng;
 use std::{collections::HashMap, path::Path, sync::Arc, time::Instant};
 use wasmer::wasmparser::Operator;
 use wasmer::{CompilerConfig, ExportIndex, Imports, Pages, Store};
@@ -113,6 +118,43 @@ fn test_depth() -> Result<()> {
     check(4 * frame_size + frame_size / 2, 4)
 }
 
+#[test]
+fn test_depth_search() -> Result<()> {
+    // in recursion.wat
+    //     the entrypoint recurses once per byte of args
+
+    let filename = "tests/recursion.wat";
+    let (compile, config, ink) = test_configs();
+
+    // programs report no peak depth, so find the least max_depth that suffices by searching
+    let least_depth = |recursion: usize| -> Result<u32> {
+        let args = vec![0; recursion];
+        let run = |max_depth| {
+            let mut config = config;
+            config.max_depth = max_depth;
+            run_both(filename, &args, &compile, config, ink).map(|x| x.kind())
+        };
+        let (mut fails, mut works) = (0, 1 << 16);
+        assert_eq!(run(works)?, UserOutcomeKind::Success);
+        while works - fails > 1 {
+            let mid = (fails + works) / 2;
+            match run(mid)? {
+                UserOutcomeKind::Success => works = mid,
+                UserOutcomeKind::OutOfStack => fails = mid,
+                kind => bail!("unexpected outcome {kind:?}"),
+            }
+        }
+        Ok(works)
+    };
+
+    // the search is exact, and deeper recursion needs proportionally more space
+    let shallow = least_depth(32)?;
+    let deep = least_depth(64)?;
+    ensure!(deep > shallow, "deep: {deep} shallow: {shallow}");
+    assert_eq!(least_depth(96)? - deep, deep - shallow);
+    Ok(())
+}
+
 #[test]
 fn test_start() -> Result<()> {
     // in start.wat
@@ -214,6 +256,261 @@ fn test_import_export_safety() -> Result<()> {
     check("tests/bad-mods/bad-import.wat", true, false)
 }
 
+#[test]
+fn test_activate_imports() -> Result<()> {
+    // test wasms
+    //     bad-import2.wat  imports a hostio with the wrong signature
+    //     bad-import3.wat  imports a hostio from an unknown module
+    //     bad-import4.wat  imports its memory
+
+    let codehash = &Bytes32::default();
+    for file in ["bad-import2", "bad-import3", "bad-import4"] {
+        let wat = std::fs::read(format!("tests/bad-mods/{file}.wat"))?;
+        let wasm = wasmer::wat2wasm(&wat)?;
+        let mut gas = u64::MAX;
+        let result = crate::native::activate(&wasm, codehash, 1, 128, true, &mut gas);
+        ensure!(result.is_err(), "{} activated", file.red());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_deterministic_activation() -> Result<()> {
+    // the module hash is consensus-critical, and nodes recompile the asm locally,
+    // so activating the same wasm must always produce identical bytes
+
+    let codehash = &random_bytes32();
+    let files = [
+        "tests/native-keccak.wat",
+        "tests/ink-left.wat",
+        "tests/traps.wat",
+        "tests/multi-export.wat",
+        "tests/accounts.wat",
+        "tests/keccak/target/wasm32-unknown-unknown/release/keccak.wasm",
+        "tests/multicall/target/wasm32-unknown-unknown/release/multicall.wasm",
+    ];
+    for file in files {
+        let wasm = wasmer::wat2wasm(&std::fs::read(file)?)?.to_vec();
+        let activate = || -> Result<_> {
+            let mut gas = u64::MAX;
+            let (asm, module, info) =
+                crate::native::activate(&wasm, codehash, 1, 128, false, &mut gas)?;
+            Ok((asm, module.hash(), module.into_bytes(), info.init_cost, gas))
+        };
+        let first = activate()?;
+        for _ in 0..4 {
+            ensure!(activate()? == first, "{} activated differently", file.red());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_console_gating() -> Result<()> {
+    // in console-entry.wat
+    //     the args length is logged to the console
+    //
+    // console hostios are only available to debug chains
+
+    let filename = "tests/console-entry.wat";
+    let wasm = wasmer::wat2wasm(&std::fs::read(filename)?)?.to_vec();
+    let codehash = &Bytes32::default();
+
+    let mut gas = u64::MAX;
+    let result = crate::native::activate(&wasm, codehash, 1, 128, false, &mut gas);
+    ensure!(result.is_err(), "console imports activated without debug");
+
+    let mut gas = u64::MAX;
+    crate::native::activate(&wasm, codehash, 1, 128, true, &mut gas)?;
+
+    let (compile, config, ink) = test_configs();
+    let mut native = TestInstance::new_linked(filename, &compile, config)?;
+    assert_eq!(run_native(&mut native, &[0; 4], ink)?, Vec::<u8>::new());
+    Ok(())
+}
+
+#[test]
+fn test_entrypoint_checks() -> Result<()> {
+    // test wasms
+    //     bad-entrypoint.wat   exports no `user_entrypoint`
+    //     bad-entrypoint2.wat  its `user_entrypoint` takes two args
+    //     bad-entrypoint3.wat  its `user_entrypoint` returns an i64
+
+    for file in ["bad-entrypoint", "bad-entrypoint2", "bad-entrypoint3"] {
+        let wat = std::fs::read(format!("tests/bad-mods/{file}.wat"))?;
+        let wasm = wasmer::wat2wasm(&wat)?;
+        let Err(err) = crate::native::validate(&wasm, 1, 128, true) else {
+            bail!("{} passed validation", file.red());
+        };
+        ensure!(
+            format!("{err:?}").contains("user_entrypoint"),
+            "{} error doesn't name the entrypoint: {err:?}",
+            file.red()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_validate() -> Result<()> {
+    let wasm =
+        |file: &str| -> Result<Vec<u8>> { Ok(wasmer::wat2wasm(&std::fs::read(file)?)?.to_vec()) };
+    let memory = wasm("tests/memory.wat")?;
+
+    let info = crate::native::validate(&memory, 1, 128, true)?;
+    assert_eq!(info.footprint, 1);
+
+    // the memory must fit within the page limit
+    assert!(crate::native::validate(&memory, 1, 0, true).is_err());
+
+    let bad = wasm("tests/bad-mods/bad-import.wat")?;
+    assert!(crate::native::validate(&bad, 1, 128, true).is_err());
+
+    // these parse fine, but import hostios that don't exist or have the wrong signature
+    for file in ["bad-import2", "bad-import3"] {
+        let bad = wasm(&format!("tests/bad-mods/{file}.wat"))?;
+        let mut output = RustBytes {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        };
+        let mut info = crate::native::validate(&memory, 1, 128, true)?;
+        let slice = GoSliceData {
+            ptr: bad.as_ptr(),
+            len: bad.len(),
+        };
+        let status = unsafe { stylus_validate(slice, 128, 1, true, &mut output, &mut info) };
+        let error = String::from_utf8(unsafe { output.into_vec() })?;
+        assert_eq!(
+            status,
+            UserOutcomeKind::Failure,
+            "{} passed validation",
+            file.red()
+        );
+        ensure!(
+            error.contains("failed to build user module"),
+            "wrong error: {error}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_activation_errors() -> Result<()> {
+    // activation fails with a message naming the check that failed
+    let check = |wasm: &[u8], page_limit: u16, expected: &str| -> Result<()> {
+        let codehash = Bytes32::default();
+        let mut gas = u64::MAX;
+        let result = crate::native::activate(wasm, &codehash, 1, page_limit, true, &mut gas);
+        let Err(err) = result else {
+            bail!("activated despite {}", expected.red());
+        };
+        let err = format!("{err:?}");
+        ensure!(err.contains(expected), "wrong error: {err}");
+        Ok(())
+    };
+    let wasm =
+        |file: &str| -> Result<Vec<u8>> { Ok(wasmer::wat2wasm(&std::fs::read(file)?)?.to_vec()) };
+
+    check(
+        &wasm("tests/bad-mods/bad-simd.wat")?,
+        128,
+        "SIMD support is not enabled",
+    )?;
+    check(&wasm("tests/memory.wat")?, 0, "memory exceeds limit")?;
+
+    // programs must export exactly one unshared memory
+    let entrypoint = r#"(func (export "user_entrypoint") (param i32) (result i32) i32.const 0)"#;
+    let memory_check = |memories: &str, expected: &str| -> Result<()> {
+        let wat = format!("(module {memories} {entrypoint})");
+        check(&wasmer::wat2wasm(wat.as_bytes())?, 128, expected)
+    };
+    memory_check("", "missing memory export with name")?;
+    memory_check("(memory 1 1)", "missing memory with export name")?;
+    memory_check(
+        r#"(memory (export "memory") 1 1) (memory 1 1)"#,
+        "multiple memories",
+    )?;
+    memory_check(
+        r#"(memory (export "memory") 1 1 shared)"#,
+        "shared memories",
+    )?;
+
+    let funcs = "(func)".repeat(4096);
+    let wat = format!(r#"(module (memory (export "memory") 0 0) {entrypoint} {funcs})"#);
+    let wasm = wasmer::wat2wasm(wat.as_bytes())?;
+    check(&wasm, 128, "too many wasm functions: 4097 > 4096")?;
+
+    // tables and data segments are bounded too, so instantiation never sees oversized modules
+    let memory = r#"(memory (export "memory") 1 1)"#;
+    let wat = format!(r#"(module {memory} (table 4097 funcref) {entrypoint})"#);
+    let wasm = wasmer::wat2wasm(wat.as_bytes())?;
+    check(&wasm, 128, "too many wasm table entries: 4097 > 4096")?;
+
+    let datas = r#"(data (i32.const 0) "")"#.repeat(129);
+    let wat = format!(r#"(module {memory} {datas} {entrypoint})"#);
+    let wasm = wasmer::wat2wasm(wat.as_bytes())?;
+    check(&wasm, 128, "too many wasm datas: 129 > 128")
+}
+
+#[test]
+fn test_asm_header() -> Result<()> {
+    let compile = test_compile_config();
+    let module = |file: &str| -> Result<Vec<u8>> {
+        let wat = std::fs::read(file)?;
+        let wasm = wasmer::wat2wasm(&wat)?;
+        crate::native::module(&wasm, compile.clone())
+    };
+
+    // serialized modules begin with wasmer's own magic and version
+    let memory = module("tests/memory.wat")?;
+    let spin = module("tests/spin.wat")?;
+    assert_eq!(memory[..8], spin[..8]);
+
+    // so asm with a damaged header is refused rather than loaded
+    let mut corrupt = memory.clone();
+    corrupt[0] ^= 0xff;
+    let (evm, evm_data) = TestEvmApi::new(compile.clone());
+    let instance = unsafe { TestInstance::deserialize(&corrupt, compile.clone(), evm, evm_data) };
+    ensure!(instance.is_err(), "deserialized a module with a bad header");
+    Ok(())
+}
+
+#[test]
+fn test_cross_compile() -> Result<()> {
+    let wat = std::fs::read("tests/memory.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let compile = test_compile_config();
+    let foreign = match cfg!(target_arch = "x86_64") {
+        true => "aarch64-unknown-linux-gnu",
+        false => "x86_64-unknown-linux-gnu+sse4.2",
+    };
+    let cross_compile =
+        |wasm: &[u8], target| crate::native::module_for_target(wasm, 128, compile.clone(), target);
+
+    let host = crate::native::module(&wasm, compile.clone())?;
+    let cross = cross_compile(&wasm, foreign)?;
+    assert_ne!(host, cross);
+
+    // the host refuses to run code for other machines
+    let (evm, evm_data) = TestEvmApi::new(compile.clone());
+    let instance = unsafe { TestInstance::deserialize(&cross, compile.clone(), evm, evm_data) };
+    let Err(err) = instance else {
+        bail!("deserialized a module for {}", foreign.red());
+    };
+    ensure!(format!("{err:?}").contains(foreign), "wrong error: {err:?}");
+
+    assert!(cross_compile(&wasm, "not-a-target").is_err());
+    assert!(cross_compile(&wasm, "x86_64-unknown-linux-gnu+bad").is_err());
+
+    // wasms must pass activation's checks first
+    let wat = std::fs::read("tests/bad-mods/bad-import2.wat")?;
+    let bad = wasmer::wat2wasm(&wat)?;
+    assert!(cross_compile(&bad, foreign).is_err());
+    assert!(cross_compile(&wasm[..wasm.len() - 1], foreign).is_err());
+    Ok(())
+}
+
 #[test]
 fn test_module_mod() -> Result<()> {
     // in module-mod.wat
@@ -278,6 +575,12 @@ fn test_heap() -> Result<()> {
         }
     }
 
+    // growing page by page in a loop is priced just the same, exhausting an insufficient budget
+    let (mut native, _) = TestInstance::new_with_evm("tests/memory.wat", &compile, config)?;
+    let outcome = native.run_main(&[128, 1], config, config.pricing.gas_to_ink(1_000_000))?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::OutOfInk);
+    assert!(native.memory_size() < Pages(128));
+
     // in memory2.wat
     //     the user program calls pay_for_memory_grow directly with malicious arguments
     //     the cost should exceed a maximum u32, consuming more gas than can ever be bought
@@ -293,6 +596,38 @@ fn test_heap() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_memory_grow_requests() -> Result<()> {
+    // in memory.wat
+    //     the input is the target size and amount to step each `memory.grow`
+    //     the output is the memory size in pages
+
+    let filename = "tests/memory.wat";
+    let (compile, config, _) = test_configs();
+    let ink = config.pricing.gas_to_ink(1_000_000);
+
+    // each memory.grow first asks Go to price the new pages, then pays that much gas
+    let ink_used = |cost: u64| -> Result<(u64, Vec<Vec<u8>>)> {
+        let handler = TestRequestHandler::default();
+        handler.respond(EvmApiMethod::AddPages, vec![], vec![], cost);
+        let mut native = handler.instance(filename, EvmData::default(), &compile, config)?;
+        let outcome = native.run_main(&[4, 1], config, ink)?;
+        let UserOutcome::Success(output) = &outcome else {
+            bail!("failed to grow: {}", outcome.red());
+        };
+        assert_eq!(output, &[4]);
+
+        let requests = handler.requests(EvmApiMethod::AddPages);
+        Ok((ink - native.ink_ready()?, requests))
+    };
+    let (free, requests) = ink_used(0)?;
+    assert_eq!(requests, vec![1_u16.to_be_bytes().to_vec(); 3]);
+
+    let (paid, _) = ink_used(1000)?;
+    assert_eq!(paid - free, config.pricing.gas_to_ink(3 * 1000));
+    Ok(())
+}
+
 #[test]
 fn test_rust() -> Result<()> {
     // in keccak.rs
@@ -357,6 +692,340 @@ fn test_fallible() -> Result<()> {
     assert_eq!(native_counts, machine_counts);
     assert_eq!(native.ink_left(), machine.ink_left());
     assert_eq!(native.stack_left(), machine.stack_left());
+
+    // failures refund whatever ink wasn't used
+    let MachineMeter::Ready(ink_left) = native.ink_left() else {
+        bail!("failure shouldn't exhaust the meter");
+    };
+    ensure!(ink_left > 0 && ink_left < ink, "bad ink left: {ink_left}");
+    Ok(())
+}
+
+#[test]
+fn test_fallible_refund() -> Result<()> {
+    let filename = "tests/fallible/target/wasm32-unknown-unknown/release/fallible.wasm";
+    let (_, config, _) = test_configs();
+    let wasm = std::fs::read(filename)?;
+    let module = crate::native::module(&wasm, CompileConfig::version(config.version, true))?;
+
+    // the gas returned through the FFI only reflects what the failing program used
+    let gas_limit = 1_000_000;
+    let (status, output, gas) = ffi_call(&module, &[0x00], config, EvmData::default(), gas_limit);
+    let error = String::from_utf8(output)?;
+    assert_eq!(status, UserOutcomeKind::Failure, "{error}");
+    ensure!(gas > 0 && gas < gas_limit, "bad gas left: {gas}");
+    Ok(())
+}
+
+#[test]
+fn test_spin() -> Result<()> {
+    // in spin.wat
+    //     the entrypoint loops forever
+
+    let (_, config, _) = test_configs();
+    let compile = CompileConfig::version(1, true);
+    let ink = config.pricing.gas_to_ink(100_000);
+
+    // only running out of ink stops a running program
+    let outcome = run_both("tests/spin.wat", &[], &compile, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::OutOfInk);
+    Ok(())
+}
+
+#[test]
+fn test_out_of_stack() -> Result<()> {
+    // in recursion.wat
+    //     the entrypoint recurses once per byte of args
+
+    let filename = "tests/recursion.wat";
+    let (compile, mut config, ink) = test_configs();
+    let args = vec![0; 4096];
+
+    // both backends stop at the caller's max_depth, reporting no data
+    config.max_depth = 1024;
+    let outcome = run_both(filename, &args, &compile, config, ink)?;
+    let (status, data) = outcome.into_data();
+    assert_eq!(status, UserOutcomeKind::OutOfStack);
+    assert!(data.is_empty());
+
+    // so raising the limit is all the same call needs
+    config.max_depth = 1 << 20;
+    let outcome = run_both(filename, &args, &compile, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Success);
+    Ok(())
+}
+
+#[test]
+fn test_metered_versions() -> Result<()> {
+    // in spin.wat
+    //     the entrypoint loops forever
+
+    let (_, config, _) = test_configs();
+    let ink = config.pricing.gas_to_ink(100_000);
+
+    // no version past the test-only version 0 lets a program run unmetered
+    let mut version = 1;
+    while let Some(compile) = CompileConfig::try_version(version, true) {
+        let outcome = run_both("tests/spin.wat", &[], &compile, config, ink)?;
+        assert_eq!(
+            outcome.kind(),
+            UserOutcomeKind::OutOfInk,
+            "version {version}"
+        );
+        version += 1;
+    }
+    ensure!(version > 2, "too few versions");
+    Ok(())
+}
+
+#[test]
+fn test_exhaustion_output() -> Result<()> {
+    // in spin.wat
+    //     the entrypoint loops forever
+    //
+    // in recursion.wat
+    //     the entrypoint recurses once per byte of args
+
+    let (_, mut config, _) = test_configs();
+    config.version = 1;
+    let module = |file: &str| -> Result<Vec<u8>> {
+        let wat = std::fs::read(file)?;
+        let wasm = wasmer::wat2wasm(&wat)?;
+        crate::native::module(&wasm, CompileConfig::version(config.version, true))
+    };
+
+    // running out of ink or stack reports no data through the FFI, and all gas is consumed
+    let spin = module("tests/spin.wat")?;
+    let (status, output, gas) = ffi_call(&spin, &[], config, EvmData::default(), 100_000);
+    assert_eq!(
+        (status, output.len(), gas),
+        (UserOutcomeKind::OutOfInk, 0, 0)
+    );
+
+    config.max_depth = 1024;
+    let recursion = module("tests/recursion.wat")?;
+    let args = vec![0; 4096];
+    let (status, output, gas) = ffi_call(&recursion, &args, config, EvmData::default(), 100_000);
+    assert_eq!(
+        (status, output.len(), gas),
+        (UserOutcomeKind::OutOfStack, 0, 0)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_loop_pricing() -> Result<()> {
+    // in loop.wat
+    //     the entrypoint loops once per byte of args
+
+    let (_, config, _) = test_configs();
+    let compile = CompileConfig::version(1, true);
+    let ink = config.pricing.gas_to_ink(1_000_000);
+
+    let ink_used = |iterations: usize| -> Result<u64> {
+        let mut native = TestInstance::new_linked("tests/loop.wat", &compile, config)?;
+        let outcome = native.run_main(&vec![0; iterations], config, ink)?;
+        ensure!(
+            outcome.kind() == UserOutcomeKind::Success,
+            "{}",
+            outcome.red()
+        );
+        Ok(ink - native.ink_ready()?)
+    };
+
+    // ink is an instruction budget: each iteration costs the same, including its block's header
+    let first = ink_used(100)?;
+    let second = ink_used(200)? - first;
+    let third = ink_used(300)? - first - second;
+    assert_eq!(second, third);
+    ensure!(
+        second / 100 > compile.pricing.ink_header_cost,
+        "iterations are too cheap: {second}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_block_header_cost() -> Result<()> {
+    // in loop.wat
+    //     the entrypoint loops once per byte of args
+
+    let (_, config, _) = test_configs();
+    let mut compile = CompileConfig::version(1, true);
+    compile.pricing.costs = |_, _| 0;
+    let header = compile.pricing.ink_header_cost;
+    let ink = config.pricing.gas_to_ink(1_000_000);
+
+    let ink_used = |iterations: usize| -> Result<u64> {
+        let mut native = TestInstance::new_linked("tests/loop.wat", &compile, config)?;
+        run_native(&mut native, &vec![0; iterations], ink)?;
+        Ok(ink - native.ink_ready()?)
+    };
+
+    // with free opcodes, ink pays only for the single check at the start of each basic block
+    let once = ink_used(1)?;
+    let hundred = ink_used(101)? - once;
+    ensure!(once > 0 && once % header == 0, "wrong ink: {once}");
+    ensure!(hundred % (100 * header) == 0, "wrong ink: {hundred}");
+    ensure!(hundred > 0, "iterations are free");
+    Ok(())
+}
+
+#[test]
+fn test_call_pricing() -> Result<()> {
+    // in loop.wat
+    //     the entrypoint loops once per byte of args
+
+    let filename = "tests/loop.wat";
+    let compile = CompileConfig::version(1, true);
+    let wat = std::fs::read(filename)?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = crate::native::module(&wasm, compile.clone())?;
+    let args = [0; 100];
+    let gas = 1_000_000;
+
+    // stylus_call converts between gas and ink only through the ink price
+    for ink_price in [1, 10_000, 30_000] {
+        let mut config = StylusConfig::default();
+        config.version = 1;
+        config.pricing.ink_price = ink_price;
+
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        let ink = config.pricing.gas_to_ink(gas);
+        let outcome = native.run_main(&args, config, ink)?;
+        assert_eq!(outcome.kind(), UserOutcomeKind::Success);
+        let expected = config.pricing.ink_to_gas(native.ink_ready()?);
+
+        let (status, _, gas_left) = ffi_call(&module, &args, config, EvmData::default(), gas);
+        assert_eq!(status, UserOutcomeKind::Success);
+        assert_eq!(gas_left, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_ink_price() -> Result<()> {
+    // in loop.wat
+    //     the entrypoint loops once per byte of args
+
+    let filename = "tests/loop.wat";
+    let compile = CompileConfig::version(1, true);
+    let args = [0; 100];
+    let ink = 100_000_000;
+
+    // opcode costs are fixed per version, so the ink price only changes what ink costs in gas
+    let mut used = vec![];
+    for ink_price in [1, 10_000, 30_000] {
+        let mut config = StylusConfig::default();
+        config.version = 1;
+        config.pricing.ink_price = ink_price;
+
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+        run_native(&mut native, &args, ink)?;
+        run_machine(&mut machine, &args, config, ink)?;
+
+        assert_eq!(native.ink_left(), machine.ink_left());
+        used.push(ink - native.ink_ready()?);
+    }
+    assert!(used.windows(2).all(|x| x[0] == x[1]), "{used:?}");
+    Ok(())
+}
+
+#[test]
+fn test_trap_names() -> Result<()> {
+    // in trap-names.wat
+    //     the entrypoint calls a named function that executes an unreachable
+
+    let (compile, config, ink) = test_configs();
+    let mut native = TestInstance::new_linked("tests/trap-names.wat", &compile, config)?;
+    let UserOutcome::Failure(err) = native.run_main(&[], config, ink)? else {
+        bail!("expected hard error");
+    };
+    let err = format!("{err:?}");
+    ensure!(
+        err.contains("deeply_nested_failure"),
+        "trap lacks name: {err}"
+    );
+
+    // stylus_call keeps the whole report, adding the step that failed
+    let wat = std::fs::read("tests/trap-names.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = crate::native::module(&wasm, compile)?;
+    let (status, output, _) = ffi_call(&module, &[], config, EvmData::default(), 1_000_000);
+    let err = String::from_utf8(output)?;
+    assert_eq!(status, UserOutcomeKind::Failure);
+    ensure!(err.starts_with("call failed"), "wrong error: {err}");
+    ensure!(
+        err.contains("deeply_nested_failure"),
+        "trap lacks name: {err}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_traps() -> Result<()> {
+    // in traps.wat
+    //     the first byte of the args selects which trap to execute
+
+    let filename = "tests/traps.wat";
+    let (compile, config, ink) = test_configs();
+
+    let traps = [
+        "unreachable",
+        "integer divide by zero",
+        "out of bounds memory access",
+        "integer overflow",
+    ];
+    for (kind, reason) in traps.into_iter().enumerate() {
+        let args = [kind as u8];
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        let UserOutcome::Failure(err) = native.run_main(&args, config, ink)? else {
+            bail!("expected {} to fail", reason.red());
+        };
+        let err = format!("{err:?}");
+        ensure!(
+            err.contains(reason),
+            "wrong reason for {}: {err}",
+            reason.red()
+        );
+
+        let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+        let outcome = machine.run_main(&args, config, ink)?;
+        assert_eq!(outcome.kind(), UserOutcomeKind::Failure);
+
+        // traps refund whatever ink wasn't used, just like other failures
+        assert_eq!(native.ink_left(), machine.ink_left());
+        assert!(matches!(native.ink_left(), MachineMeter::Ready(left) if left > 0));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_differential() -> Result<()> {
+    // programs that don't consult the EVM must behave identically in both backends,
+    // including when they trap or run out of ink partway through
+
+    let (compile, config, _) = test_configs();
+    let fixtures = [
+        "tests/native-keccak.wat",
+        "tests/ink-left.wat",
+        "tests/traps.wat",
+        "tests/multi-export.wat",
+        "tests/exit-early/exit-early.wat",
+    ];
+    let mut rng = rand::thread_rng();
+    for path in fixtures {
+        for _ in 0..8 {
+            let len = rng.gen_range(0..256);
+            let args: Vec<u8> = (0..len).map(|_| rng.gen_range(0..4)).collect();
+            let ink = match rng.gen_bool(0.5) {
+                true => rng.gen_range(0..100_000),
+                false => random_ink(1_000_000),
+            };
+            run_both(path, &args, &compile, config, ink)?;
+        }
+    }
     Ok(())
 }
 
@@ -391,6 +1060,304 @@ fn test_storage() -> Result<()> {
     check_instrumentation(native, machine)
 }
 
+#[test]
+fn test_storage_warmth() -> Result<()> {
+    // in storage.rs
+    //     an input starting with 0x00 will induce a storage read
+
+    let filename = "tests/storage/target/wasm32-unknown-unknown/release/storage.wasm";
+    let (compile, config, ink) = test_configs();
+
+    let mut load_args = vec![0x00];
+    load_args.extend(crypto::keccak(filename.as_bytes()));
+
+    let (mut native, _) = TestInstance::new_with_evm(filename, &compile, config)?;
+    let mut ink_used = || -> Result<u64> {
+        run_native(&mut native, &load_args, ink)?;
+        Ok(ink - native.ink_ready()?)
+    };
+    let cold = ink_used()?;
+    let warm = ink_used()?;
+    let diff = config.pricing.gas_to_ink(COLD_SLOAD_GAS - WARM_SLOAD_GAS);
+    ensure!(cold - warm == diff, "cold: {cold} warm: {warm}");
+    Ok(())
+}
+
+#[test]
+fn test_storage_flush() -> Result<()> {
+    // in storage-flush.wat
+    //     the args are a key and value to cache, which is then flushed
+
+    let (compile, config, _) = test_configs();
+    let gas = 1_000_000;
+    let ink = config.pricing.gas_to_ink(gas);
+
+    let mut args = vec![];
+    args.extend(random_bytes32());
+    args.extend(random_bytes32());
+
+    let handler = TestRequestHandler::default();
+    let path = "tests/storage-flush.wat";
+    let mut native = handler.instance(path, EvmData::default(), &compile, config)?;
+    let outcome = native.run_main(&args, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Success);
+
+    // writes reach Go as the slots to set, leaving Go to apply them and tabulate any refunds
+    let flushes = handler.requests(EvmApiMethod::SetTrieSlots);
+    assert_eq!(flushes.len(), 1);
+    let (gas_left, slots) = flushes[0].split_at(8);
+    let gas_left = u64::from_be_bytes(gas_left.try_into()?);
+    ensure!(gas_left > 0 && gas_left < gas, "bad gas left: {gas_left}");
+    assert_eq!(slots, &args[..]);
+    Ok(())
+}
+
+#[test]
+fn test_storage_flush_failure() -> Result<()> {
+    // in storage-flush.wat
+    //     the args are a key and value to cache, which is then flushed
+
+    let (compile, config, ink) = test_configs();
+    let args = [random_bytes32().0, random_bytes32().0].concat();
+
+    // when Go can't apply the writes, the program fails with Go's reason rather than the host
+    for status in [
+        EvmApiStatus::Failure,
+        EvmApiStatus::OutOfGas,
+        EvmApiStatus::WriteProtection,
+    ] {
+        let handler = TestRequestHandler::default();
+        let result = vec![status.into()];
+        handler.respond(EvmApiMethod::SetTrieSlots, result, vec![], 0);
+
+        let path = "tests/storage-flush.wat";
+        let mut native = handler.instance(path, EvmData::default(), &compile, config)?;
+        let outcome = native.run_main(&args, config, ink)?;
+        assert_eq!(outcome.kind(), UserOutcomeKind::Failure);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_access_costs() -> Result<()> {
+    // in storage-load.wat
+    //     the args are a key whose value is loaded and returned
+    //
+    // in accounts.wat
+    //     the args are an address whose balance, code hash, and code are returned
+
+    let (compile, config, _) = test_configs();
+    let ink = config.pricing.gas_to_ink(1_000_000);
+
+    let ink_used = |path: &str, args: &[u8], method: EvmApiMethod, cost: u64| -> Result<u64> {
+        let handler = TestRequestHandler::default();
+        handler.respond(method, vec![0; 32], vec![], cost);
+
+        let mut native = handler.instance(path, EvmData::default(), &compile, config)?;
+        let outcome = native.run_main(args, config, ink)?;
+        ensure!(
+            outcome.kind() == UserOutcomeKind::Success,
+            "{}",
+            outcome.red()
+        );
+        Ok(ink - native.ink_ready()?)
+    };
+
+    // Go prices each access against the tx's access list, and the program pays what it says
+    let (cold, warm) = (COLD_SLOAD_GAS, WARM_SLOAD_GAS);
+    let path = "tests/storage-load.wat";
+    let key = random_bytes32();
+    let diff = ink_used(path, &key[..], EvmApiMethod::GetBytes32, cold)?
+        - ink_used(path, &key[..], EvmApiMethod::GetBytes32, warm)?;
+    assert_eq!(diff, config.pricing.gas_to_ink(cold - warm));
+
+    let (cold, warm) = (COLD_ACCOUNT_GAS, WARM_SLOAD_GAS);
+    let path = "tests/accounts.wat";
+    let args = [&random_bytes20()[..], &[0; 8][..]].concat();
+    let diff = ink_used(path, &args, EvmApiMethod::AccountBalance, cold)?
+        - ink_used(path, &args, EvmApiMethod::AccountBalance, warm)?;
+    assert_eq!(diff, config.pricing.gas_to_ink(cold - warm));
+    Ok(())
+}
+
+#[test]
+fn test_storage_backends() -> Result<()> {
+    // in storage-flush.wat
+    //     the args are a key and value to cache, which is then flushed
+
+    let (compile, config, ink) = test_configs();
+    let path = "tests/storage-flush.wat";
+    let (key, value) = (random_bytes32(), random_bytes32());
+    let args = [key.0, value.0].concat();
+
+    // the same program runs against any EvmApi, here the in-memory one
+    let (mut evm, evm_data) = TestEvmApi::new(compile.clone());
+    let mut native = TestInstance::from_path(path, evm.clone(), evm_data, &compile, config)?;
+    run_native(&mut native, &args, ink)?;
+    assert_eq!(evm.get_bytes32(key).0, value);
+
+    // and the requestor Go drives, which sees the same write
+    let handler = TestRequestHandler::default();
+    let mut native = handler.instance(path, evm_data, &compile, config)?;
+    let outcome = native.run_main(&args, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Success);
+    let flushes = handler.requests(EvmApiMethod::SetTrieSlots);
+    assert_eq!(flushes.len(), 1);
+    assert_eq!(flushes[0][8..], args);
+    Ok(())
+}
+
+#[test]
+fn test_create_requests() -> Result<()> {
+    // in create1.wat
+    //     the args are an endowment and init code to deploy
+    //     the output is the new address followed by the revert data length
+
+    let (compile, config, ink) = test_configs();
+    let path = "tests/create1.wat";
+    let endowment = random_bytes32();
+    let code = [0x60, 0x00, 0x60, 0x00, 0xf3]; // PUSH1 0 PUSH1 0 RETURN
+    let args = [&endowment[..], &code[..]].concat();
+
+    // Go runs the init code, replying with a success byte and the address
+    let address = random_bytes20();
+    let handler = TestRequestHandler::default();
+    let mut result = vec![1];
+    result.extend(address.0);
+    handler.respond(EvmApiMethod::Create1, result, vec![7; 3], 0);
+
+    let mut native = handler.instance(path, EvmData::default(), &compile, config)?;
+    let UserOutcome::Success(output) = native.run_main(&args, config, ink)? else {
+        bail!("create failed");
+    };
+    assert_eq!(output[..20], address[..]);
+    assert_eq!(output[20..], 3_u32.to_le_bytes());
+
+    // the request holds the gas to give, the endowment, and the init code
+    let requests = handler.requests(EvmApiMethod::Create1);
+    assert_eq!(requests.len(), 1);
+    let (gas, rest) = requests[0].split_at(8);
+    ensure!(u64::from_be_bytes(gas.try_into()?) > 0, "no gas given");
+    assert_eq!(rest, &args[..]);
+
+    // a failure byte fails the program with Go's error
+    let handler = TestRequestHandler::default();
+    let mut result = vec![0];
+    result.extend(b"create failed in Go");
+    handler.respond(EvmApiMethod::Create1, result, vec![], 0);
+
+    let mut native = handler.instance(path, EvmData::default(), &compile, config)?;
+    let UserOutcome::Failure(err) = native.run_main(&args, config, ink)? else {
+        bail!("create succeeded");
+    };
+    ensure!(
+        format!("{err:?}").contains("create failed in Go"),
+        "wrong error: {err:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_transfer_requests() -> Result<()> {
+    // in transfer.wat
+    //     the args are an address and value to send it, with no calldata and no gas
+    //     the output is the status of the call
+
+    let (compile, config, ink) = test_configs();
+    let (address, value) = (random_bytes20(), random_bytes32());
+    let args = [&address[..], &value[..]].concat();
+
+    let transfer = |status: UserOutcomeKind| -> Result<(Vec<u8>, Vec<u8>)> {
+        let handler = TestRequestHandler::default();
+        handler.respond(EvmApiMethod::ContractCall, vec![status as u8], vec![], 0);
+
+        let path = "tests/transfer.wat";
+        let mut native = handler.instance(path, EvmData::default(), &compile, config)?;
+        let output = match native.run_main(&args, config, ink)? {
+            UserOutcome::Success(output) => output,
+            outcome => bail!("transfer failed: {}", outcome.red()),
+        };
+        let requests = handler.requests(EvmApiMethod::ContractCall);
+        assert_eq!(requests.len(), 1);
+        Ok((output, requests[0].clone()))
+    };
+
+    // the request asks for no gas, so Go only forwards the stipend
+    let (output, request) = transfer(UserOutcomeKind::Success)?;
+    assert_eq!(output, [0]);
+    assert_eq!(request[..20], address[..]);
+    assert_eq!(request[20..52], value[..]);
+    let gas_left = u64::from_be_bytes(request[52..60].try_into()?);
+    let gas_req = u64::from_be_bytes(request[60..68].try_into()?);
+    ensure!(gas_left > 0, "no gas left");
+    assert_eq!(gas_req, 0);
+    assert_eq!(request.len(), 68);
+
+    // and a failed transfer hands the status to the program rather than trapping
+    let (output, _) = transfer(UserOutcomeKind::Revert)?;
+    assert_eq!(output, [UserOutcomeKind::Revert as u8]);
+    Ok(())
+}
+
+#[test]
+fn test_logs() -> Result<()> {
+    // in log.rs
+    //     the first byte is the number of topics
+    //     the next 32 bytes of each topic follow, with the rest being the log data
+
+    let filename = "tests/log/target/wasm32-unknown-unknown/release/log.wasm";
+    let (compile, config, ink) = test_configs();
+
+    let topics = rand::random::<u8>() % 5;
+    let data = random_bytes32();
+    let mut args = vec![topics];
+    for _ in 0..topics {
+        args.extend(random_bytes32());
+    }
+    args.extend(data);
+
+    let (mut native, evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    run_native(&mut native, &args, ink)?;
+    assert_eq!(evm.logs(), vec![(args[1..].to_vec(), topics.into())]);
+
+    // more than 4 topics isn't allowed, leaving no log
+    let (mut native, evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    let mut args = vec![5];
+    args.extend([0; 5 * 32]);
+    assert_eq!(
+        native.run_main(&args, config, ink)?.kind(),
+        UserOutcomeKind::Failure
+    );
+    assert!(evm.logs().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_log_requests() -> Result<()> {
+    // in log.rs
+    //     the first byte is the number of topics
+    //     the next 32 bytes of each topic follow, with the rest being the log data
+
+    let filename = "tests/log/target/wasm32-unknown-unknown/release/log.wasm";
+    let (compile, config, ink) = test_configs();
+
+    let mut args = vec![1];
+    args.extend(random_bytes32());
+    args.extend(random_bytes32());
+
+    let handler = TestRequestHandler::default();
+    let mut native = handler.instance(filename, EvmData::default(), &compile, config)?;
+    let outcome = native.run_main(&args, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Success);
+
+    // programs keep no state of their own, so Go can discard a log by reverting the StateDB
+    let mut expected = 1_u32.to_be_bytes().to_vec();
+    expected.extend(&args[1..]);
+    assert_eq!(handler.requests(EvmApiMethod::EmitLog), vec![expected]);
+    assert!(handler.requests(EvmApiMethod::SetTrieSlots).is_empty());
+    Ok(())
+}
+
 #[test]
 fn test_calls() -> Result<()> {
     // in call.rs
@@ -497,3 +1464,911 @@ fn test_exit_early() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn test_io_pricing() -> Result<()> {
+    // in exit-early.wat
+    //     the input is returned as the output
+    //     the status code is the first byte
+    //
+    // reading args and writing the result charge per byte beyond the first 32
+
+    let (compile, config, ink) = test_configs();
+    let mut native = TestInstance::new_linked("tests/exit-early/exit-early.wat", &compile, config)?;
+
+    let mut ink_used = |len: usize| -> Result<u64> {
+        let outcome = native.run_main(&vec![0; len], config, ink)?;
+        ensure!(
+            outcome.kind() == UserOutcomeKind::Success,
+            "{}",
+            outcome.red()
+        );
+        Ok(ink - native.ink_ready()?)
+    };
+    let small = ink_used(32)?;
+    let large = ink_used(32 + 1024)?;
+    assert_eq!(large - small, (30 + 55 + 55) * 1024);
+    Ok(())
+}
+
+#[test]
+fn test_float_determinism() -> Result<()> {
+    // in float-math.wat
+    //     the results of several float ops on two f64 args are written to the output
+
+    let filename = "tests/float-math.wat";
+    let (compile, config, ink) = test_configs();
+
+    let mut cases = vec![
+        (1.0, 3.0),
+        (f64::MIN_POSITIVE, 3.0),
+        (1e308, 1e-308),
+        (-0.0, 0.5),
+    ];
+    for _ in 0..32 {
+        let a = (rand::random::<f64>() - 0.5) * 1e12;
+        let b = (rand::random::<f64>() - 0.5) * 1e-6;
+        cases.push((a, b));
+    }
+
+    for (a, b) in cases {
+        let mut args = f64::to_le_bytes(a).to_vec();
+        args.extend(f64::to_le_bytes(b));
+
+        // run_both checks that the native and prover outputs match bit for bit
+        let outcome = run_both(filename, &args, &compile, config, ink)?;
+        let UserOutcome::Success(output) = &outcome else {
+            bail!("float ops failed: {}", outcome.red());
+        };
+        let result = |i: usize| f64::from_le_bytes(output[8 * i..8 * i + 8].try_into().unwrap());
+        assert_eq!(result(0).to_bits(), (a + b).to_bits());
+        assert_eq!(result(2).to_bits(), (a * b).to_bits());
+        assert_eq!(result(3).to_bits(), (a / b).to_bits());
+        assert_eq!(result(4).to_bits(), a.abs().sqrt().to_bits());
+    }
+
+    // but pricing_v1 charges u64::MAX for each float op, so deployed programs can't run them
+    let compile = CompileConfig::version(1, true);
+    let outcome = run_both(filename, &[0; 16], &compile, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::OutOfInk);
+    Ok(())
+}
+
+#[test]
+fn test_revert_gas() -> Result<()> {
+    // in exit-early.wat
+    //     the input is returned as the output
+    //     the status code is the first byte
+    //
+    // reverting consumes only the ink used, exactly as succeeding does
+
+    let filename = "tests/exit-early/exit-early.wat";
+    let (compile, config, ink) = test_configs();
+
+    let ink_used = |status: u8| -> Result<(UserOutcomeKind, u64)> {
+        let mut args = vec![status];
+        args.extend([0; 63]);
+
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        let outcome = native.run_main(&args, config, ink)?;
+        let native_left = native.ink_ready()?;
+
+        let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+        machine.run_main(&args, config, ink)?;
+        assert_eq!(machine.ink_left(), MachineMeter::Ready(native_left));
+        Ok((outcome.kind(), ink - native_left))
+    };
+    let (success, success_ink) = ink_used(0)?;
+    let (revert, revert_ink) = ink_used(1)?;
+    assert_eq!(success, UserOutcomeKind::Success);
+    assert_eq!(revert, UserOutcomeKind::Revert);
+    assert_eq!(success_ink, revert_ink);
+    assert!(!revert.burns_all_gas());
+    Ok(())
+}
+
+#[test]
+fn test_ink_left() -> Result<()> {
+    // in ink-left.wat
+    //     the ink left is written twice, then the gas left, then the ink left again
+
+    let filename = "tests/ink-left.wat";
+    let (compile, config, ink) = test_configs();
+
+    let mut native = TestInstance::new_linked(filename, &compile, config)?;
+    let output = run_native(&mut native, &[], ink)?;
+    let word = |i: usize| u64::from_le_bytes(output[8 * i..8 * i + 8].try_into().unwrap());
+    let (ink_1, ink_2, gas, ink_3) = (word(0), word(1), word(2), word(3));
+
+    // values reflect the ink left after paying for the hostio itself
+    assert!(ink_1 <= ink - pricing::HOSTIO_INK);
+    assert!(ink_2 <= ink_1 - pricing::HOSTIO_INK);
+    assert!(ink_3 < ink_2);
+    assert!(gas <= config.pricing.ink_to_gas(ink_2));
+    assert!(gas >= config.pricing.ink_to_gas(ink_3));
+
+    let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+    assert_eq!(run_machine(&mut machine, &[], config, ink)?, output);
+    Ok(())
+}
+
+#[test]
+fn test_ink_left_prices() -> Result<()> {
+    // in ink-left.wat
+    //     the ink left is written twice, then the gas left, then the ink left again
+
+    let filename = "tests/ink-left.wat";
+    let (compile, mut config, _) = test_configs();
+    let ink = 1_000_000_000;
+    let hostio = pricing::HOSTIO_INK;
+
+    // with free opcodes, each read sees exactly the ink its predecessors left, at any price
+    for ink_price in [1, 10_000, 30_000] {
+        config.pricing.ink_price = ink_price;
+
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        let output = run_native(&mut native, &[], ink)?;
+        let words: Vec<_> = output
+            .chunks(8)
+            .map(|x| u64::from_le_bytes(x.try_into().unwrap()))
+            .collect();
+        let gas = config.pricing.ink_to_gas(ink - 3 * hostio);
+        assert_eq!(
+            words,
+            [ink - hostio, ink - 2 * hostio, gas, ink - 4 * hostio]
+        );
+
+        let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+        assert_eq!(run_machine(&mut machine, &[], config, ink)?, output);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cached_determinism() -> Result<()> {
+    // in ink-left.wat
+    //     the ink left is written twice, then the gas left, then the ink left again
+    //
+    // cached instances share a module and engine, so alternating configs must never leak state
+
+    let filename = "tests/ink-left.wat";
+    let wat = std::fs::read(filename)?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = crate::native::module(&wasm, CompileConfig::version(1, true))?;
+
+    let mut config_a = StylusConfig::default();
+    config_a.pricing.ink_price = 10000;
+    let mut config_b = config_a;
+    config_b.pricing.ink_price = 1;
+    let ink = random_ink(1_000_000);
+
+    let (evm, mut evm_data) = TestEvmApi::new(CompileConfig::version(1, true));
+    evm_data.module_hash = random_bytes32();
+
+    let mut expected = [None, None];
+    for i in 0..1000 {
+        let config = [config_a, config_b][i % 2];
+        let mut native = unsafe {
+            TestInstance::deserialize_cached(&module, 1, evm.clone(), evm_data, 0, true)?
+        };
+        let outcome = native.run_main(&[], config, ink)?;
+        let result = (format!("{outcome}"), native.ink_ready()?);
+
+        match &expected[i % 2] {
+            Some(expected) => assert_eq!(*expected, result),
+            None => expected[i % 2] = Some(result),
+        }
+    }
+    assert_ne!(expected[0], expected[1]);
+    Ok(())
+}
+
+#[test]
+fn test_run_export() -> Result<()> {
+    // in multi-export.wat
+    //     the entrypoint outputs "main" and the `view` export outputs "view"
+
+    let filename = "tests/multi-export.wat";
+    let (compile, config, ink) = test_configs();
+
+    let outcome = run_both(filename, &[], &compile, config, ink)?;
+    assert_eq!(outcome.into_data().1, b"main");
+
+    let outcome = run_both_export(filename, "view", &[], &compile, config, ink)?;
+    assert_eq!(outcome.into_data().1, b"view");
+
+    for name in ["missing", "wrong_shape"] {
+        let outcome = run_both_export(filename, name, &[], &compile, config, ink)?;
+        assert_eq!(outcome.kind(), UserOutcomeKind::Failure);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_float_nans() -> Result<()> {
+    // in float-nan.wat
+    //     the bits of several NaN-producing float ops are written to the output
+
+    let filename = "tests/float-nan.wat";
+    let (compile, config, ink) = test_configs();
+
+    let check_nans = |output: &[u8]| {
+        let f64_nan = |i: usize| f64::from_le_bytes(output[i..i + 8].try_into().unwrap());
+        let f32_nan = |i: usize| f32::from_le_bytes(output[i..i + 4].try_into().unwrap());
+        assert!(f64_nan(0).is_nan() && f64_nan(8).is_nan() && f32_nan(16).is_nan());
+    };
+
+    let mut native = TestInstance::new_linked(filename, &compile, config)?;
+    let output = run_native(&mut native, &[], ink)?;
+    check_nans(&output);
+
+    // the compiler canonicalizes NaNs, discarding the input's payload
+    let canonical = f64::NAN.to_bits().to_le_bytes();
+    assert_eq!(output[0..8], canonical);
+    assert_eq!(output[8..16], canonical);
+
+    let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+    check_nans(&run_machine(&mut machine, &[], config, ink)?);
+    Ok(())
+}
+
+#[test]
+fn test_native_keccak() -> Result<()> {
+    // in native-keccak.wat
+    //     the output is the keccak of the input
+    //
+    // hashing charges per word beyond the first two
+
+    let filename = "tests/native-keccak.wat";
+    let (compile, config, ink) = test_configs();
+
+    let vectors: [(&[u8], &str); 2] = [
+        (
+            b"",
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        ),
+        (
+            &[0],
+            "bc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a",
+        ),
+    ];
+    for (preimage, hash) in vectors {
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        let output = run_native(&mut native, preimage, ink)?;
+        assert_eq!(hex::encode(output), hash);
+
+        let mut machine = Machine::from_user_path(Path::new(filename), &compile)?;
+        let output = run_machine(&mut machine, preimage, config, ink)?;
+        assert_eq!(hex::encode(output), hash);
+    }
+
+    // random inputs hash identically in native and the prover, for the same ink
+    let len = rand::thread_rng().gen_range(0..1024);
+    let preimage: Vec<u8> = (0..len).map(|_| rand::random()).collect();
+    let outcome = run_both(filename, &preimage, &compile, config, ink)?;
+    let UserOutcome::Success(output) = &outcome else {
+        bail!("failed to hash: {}", outcome.red());
+    };
+    assert_eq!(output, &crypto::keccak(&preimage));
+
+    // an input spanning multiple pages
+    let preimage: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+    let mut native = TestInstance::new_linked(filename, &compile, config)?;
+    let output = run_native(&mut native, &preimage, u64::MAX)?;
+    assert_eq!(output, crypto::keccak(&preimage));
+
+    let ink_used = |len: usize| -> Result<u64> {
+        let mut native = TestInstance::new_linked(filename, &compile, config)?;
+        run_native(&mut native, &vec![0; len], ink)?;
+        Ok(ink - native.ink_ready()?)
+    };
+    let small = ink_used(64)?;
+    let word = 30 * 32 + 21000; // reading the arg and hashing it
+    assert_eq!(ink_used(96)? - small, word);
+    assert_eq!(ink_used(64 + 32 * 8)? - small, 8 * word);
+    Ok(())
+}
+
+#[test]
+fn test_hostio_requests() -> Result<()> {
+    // in native-keccak.wat
+    //     the output is the keccak of the input
+
+    let (compile, config, ink) = test_configs();
+    let mut evm_data = EvmData::default();
+    evm_data.tracing = true;
+
+    let handler = TestRequestHandler::default();
+    let mut native = handler.instance("tests/native-keccak.wat", evm_data, &compile, config)?;
+    let args = random_bytes32();
+    let outcome = native.run_main(&args[..], config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Success);
+
+    // each traced hostio reaches Go with its ink bounds, name, args, and outputs
+    let mut traced = vec![];
+    for request in handler.requests(EvmApiMethod::CaptureHostIO) {
+        let start_ink = u64::from_be_bytes(request[0..8].try_into()?);
+        let end_ink = u64::from_be_bytes(request[8..16].try_into()?);
+        let len = |i: usize| u16::from_be_bytes([request[i], request[i + 1]]) as usize;
+        let (name_len, args_len, outs_len) = (len(16), len(18), len(20));
+
+        let rest = &request[22..];
+        assert_eq!(rest.len(), name_len + args_len + outs_len);
+        let (name, rest) = rest.split_at(name_len);
+        let (hostio_args, outs) = rest.split_at(args_len);
+        ensure!(start_ink >= end_ink, "ink grew: {start_ink} -> {end_ink}");
+        traced.push((
+            String::from_utf8(name.to_vec())?,
+            hostio_args.to_vec(),
+            outs.to_vec(),
+        ));
+    }
+
+    let keccak = traced.iter().find(|x| x.0 == "native_keccak256").unwrap();
+    assert_eq!(keccak.1, &args[..]);
+    assert_eq!(keccak.2, crypto::keccak(args).to_vec());
+    Ok(())
+}
+
+#[test]
+fn test_hostio_trace() -> Result<()> {
+    // in native-keccak.wat
+    //     the output is the keccak of the input
+
+    let (compile, config, ink) = test_configs();
+    let (evm, mut evm_data) = TestEvmApi::new(compile.clone());
+
+    let file = "tests/native-keccak.wat";
+    let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+    run_native(&mut native, &[0; 64], ink)?;
+    assert!(evm.hostios().is_empty()); // nothing's captured unless tracing
+
+    evm_data.tracing = true;
+    let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+    run_native(&mut native, &[0; 64], ink)?;
+
+    let hostios = evm.hostios();
+    let names: Vec<_> = hostios.iter().map(|x| x.0.as_str()).collect();
+    let expected = [
+        "user_entrypoint",
+        "read_args",
+        "native_keccak256",
+        "write_result",
+        "user_returned",
+    ];
+    assert_eq!(names, expected);
+
+    // each hostio starts after the last one ended and pays something
+    let mut last_ink = ink;
+    for (name, start_ink, end_ink) in &hostios[1..4] {
+        ensure!(*start_ink <= last_ink, "{} started too early", name.red());
+        ensure!(end_ink < start_ink, "{} was free", name.red());
+        last_ink = *end_ink;
+    }
+    assert!(native.ink_ready()? <= last_ink);
+    Ok(())
+}
+
+#[test]
+fn test_trace_ink() -> Result<()> {
+    // in native-keccak.wat
+    //     the output is the keccak of the input
+
+    let (compile, config, ink) = test_configs();
+    let (evm, mut evm_data) = TestEvmApi::new(compile.clone());
+
+    let file = "tests/native-keccak.wat";
+    let mut ink_left = |tracing| -> Result<u64> {
+        evm_data.tracing = tracing;
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        run_native(&mut native, &[0; 64], ink)?;
+        Ok(native.ink_ready()?)
+    };
+    let untraced = ink_left(false)?;
+    assert!(evm.hostios().is_empty()); // nothing is captured unless tracing
+
+    // tracing observes the ink used without changing it
+    assert_eq!(ink_left(true)?, untraced);
+    ensure!(!evm.hostios().is_empty(), "nothing was traced");
+    Ok(())
+}
+
+#[test]
+fn test_hostio_prices() -> Result<()> {
+    // in native-keccak.wat
+    //     the output is the keccak of the input
+
+    let (compile, config, ink) = test_configs();
+    let (evm, mut evm_data) = TestEvmApi::new(compile.clone());
+    evm_data.tracing = true;
+
+    let file = "tests/native-keccak.wat";
+    let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+    run_native(&mut native, &[0; 96], ink)?;
+
+    // each hostio pays its own base cost plus a cost per byte or word beyond the first few
+    let spent: Vec<_> = evm.hostios()[1..4].iter().map(|x| x.1 - x.2).collect();
+    let read_args = pricing::HOSTIO_INK + 5040 + 30 * (96 - 32);
+    let keccak = 121800 + 21000; // the one word beyond the first two
+    let write_result = pricing::HOSTIO_INK + 2 * 16381;
+    assert_eq!(spent, [read_args, keccak, write_result]);
+    Ok(())
+}
+
+#[test]
+fn test_args_pricing() -> Result<()> {
+    // in native-keccak.wat
+    //     the args are read up front, then hashed
+
+    let (compile, config, ink) = test_configs();
+    let read_args = |len: usize| -> Result<u64> {
+        let (evm, mut evm_data) = TestEvmApi::new(compile.clone());
+        evm_data.tracing = true;
+
+        let file = "tests/native-keccak.wat";
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        run_native(&mut native, &vec![0; len], ink)?;
+
+        let hostios = evm.hostios();
+        let (name, before, after) = &hostios[1];
+        assert_eq!(name, "read_args");
+        Ok(before - after)
+    };
+
+    // the first word is covered by the base cost, and every byte after it is paid for
+    let base = pricing::HOSTIO_INK + 5040;
+    assert_eq!(read_args(0)?, base);
+    assert_eq!(read_args(32)?, base);
+    assert_eq!(read_args(33)?, base + 30);
+    assert_eq!(read_args(1024)?, base + 30 * (1024 - 32));
+    Ok(())
+}
+
+#[test]
+fn test_return_data_pricing() -> Result<()> {
+    // in return-size.wat
+    //     memory grows to fit the size in the last bytes of the args, then that much is returned
+
+    let file = "tests/return-size.wat";
+    let (compile, config, _) = test_configs();
+    let ink = 10_000_000;
+
+    // a word of return data is affordable
+    let outcome = run_both(file, &32_u32.to_be_bytes(), &compile, config, ink)?;
+    let UserOutcome::Success(output) = &outcome else {
+        bail!("small return failed: {}", outcome.red());
+    };
+    assert_eq!(output.len(), 32);
+
+    // but each byte past it is paid for twice, so a MiB is far beyond the budget
+    let size = 1 << 20;
+    assert!(2 * (16381 + 55 * (size - 32)) > ink);
+    let outcome = run_both(file, &(size as u32).to_be_bytes(), &compile, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::OutOfInk);
+    Ok(())
+}
+
+#[test]
+fn test_cached_instances() -> Result<()> {
+    // in counter.wat
+    //     a global and a word of memory count the calls made to the instance
+
+    let (_, config, _) = test_configs();
+    let wat = std::fs::read("tests/counter.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = crate::native::module(&wasm, CompileConfig::version(config.version, true))?;
+    let fresh = [1, 0, 0, 0, 1, 0, 0, 0];
+
+    // later calls hit the cache, but each gets its own instance
+    for _ in 0..3 {
+        let (status, output, _) = ffi_call(&module, &[], config, EvmData::default(), 1_000_000);
+        assert_eq!(status, UserOutcomeKind::Success);
+        assert_eq!(output, fresh);
+    }
+
+    // which holds for concurrent calls too
+    let module = Arc::new(module);
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let module = module.clone();
+            std::thread::spawn(move || {
+                ffi_call(&module, &[], config, EvmData::default(), 1_000_000)
+            })
+        })
+        .collect();
+    for thread in threads {
+        let (status, output, _) = thread.join().unwrap();
+        assert_eq!(status, UserOutcomeKind::Success);
+        assert_eq!(output, fresh);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_non_payable() -> Result<()> {
+    // in non-payable.wat
+    //     the program reverts when called with value, as the SDK's router does for such methods
+
+    let (_, config, _) = test_configs();
+    let wat = std::fs::read("tests/non-payable.wat")?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = crate::native::module(&wasm, CompileConfig::version(config.version, true))?;
+
+    let mut evm_data = EvmData::default();
+    let (status, ..) = ffi_call(&module, &[], config, evm_data, 1_000_000);
+    assert_eq!(status, UserOutcomeKind::Success);
+
+    evm_data.msg_value = random_bytes32();
+    let (status, ..) = ffi_call(&module, &[], config, evm_data, 1_000_000);
+    assert_eq!(status, UserOutcomeKind::Revert);
+    Ok(())
+}
+
+#[test]
+fn test_write_result_pricing() -> Result<()> {
+    // in return-size.wat
+    //     memory grows to fit the size in the last bytes of the args, then that much is returned
+
+    let (compile, config, _) = test_configs();
+    let ink = config.pricing.gas_to_ink(10_000_000);
+    let write_result = |size: u32| -> Result<u64> {
+        let (evm, mut evm_data) = TestEvmApi::new(compile.clone());
+        evm_data.tracing = true;
+
+        let file = "tests/return-size.wat";
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        assert_eq!(
+            run_native(&mut native, &size.to_be_bytes(), ink)?.len(),
+            size as usize
+        );
+
+        let hostios = evm.hostios();
+        let (_, before, after) = hostios.iter().find(|x| x.0 == "write_result").unwrap();
+        Ok(before - after)
+    };
+
+    // the cost of return data grows linearly with its length, with no step or cap
+    let base = write_result(32)?;
+    assert_eq!(base, pricing::HOSTIO_INK + 2 * 16381);
+    for size in [33, 1000, 65536, 200_000] {
+        let per_byte = 2 * 55 * (size as u64 - 32);
+        assert_eq!(write_result(size)?, base + per_byte);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_evm_context() -> Result<()> {
+    // in evm-context.wat
+    //     each field of the EVM context is written to the output in turn
+
+    let (compile, config, ink) = test_configs();
+    let (evm, mut evm_data) = TestEvmApi::new(compile.clone());
+    evm_data.msg_sender = random_bytes20();
+    evm_data.contract_address = random_bytes20();
+    evm_data.tx_origin = random_bytes20();
+    evm_data.block_coinbase = random_bytes20();
+    evm_data.msg_value = random_bytes32();
+    evm_data.block_basefee = random_bytes32();
+    evm_data.tx_gas_price = random_bytes32();
+    evm_data.block_number = rand::random();
+    evm_data.chainid = rand::random();
+    evm_data.block_timestamp = rand::random();
+    evm_data.block_gas_limit = rand::random();
+    evm_data.reentrant = 1;
+
+    let file = "tests/evm-context.wat";
+    let mut native = TestInstance::from_path(file, evm, evm_data, &compile, config)?;
+    let output = run_native(&mut native, &[], ink)?;
+
+    let mut expected = vec![];
+    expected.extend(evm_data.msg_sender);
+    expected.extend(evm_data.contract_address);
+    expected.extend(evm_data.tx_origin);
+    expected.extend(evm_data.block_coinbase);
+    expected.extend(evm_data.msg_value);
+    expected.extend(evm_data.block_basefee);
+    expected.extend(evm_data.tx_gas_price);
+    expected.extend(evm_data.block_number.to_le_bytes());
+    expected.extend(evm_data.chainid.to_le_bytes());
+    expected.extend(evm_data.block_timestamp.to_le_bytes());
+    expected.extend(evm_data.block_gas_limit.to_le_bytes());
+    expected.extend(evm_data.reentrant.to_le_bytes());
+    assert_eq!(hex::encode(output), hex::encode(&expected));
+
+    // stylus_call passes the context through to the same hostios
+    let wat = std::fs::read(file)?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let module = crate::native::module(&wasm, CompileConfig::version(config.version, true))?;
+    let (status, output, _) = ffi_call(&module, &[], config, evm_data, 1_000_000);
+    assert_eq!(status, UserOutcomeKind::Success);
+    assert_eq!(hex::encode(output), hex::encode(expected));
+    Ok(())
+}
+
+#[test]
+fn test_static_storage() -> Result<()> {
+    // in multicall.rs
+    //     a kind of 0x02 makes a static call
+    //
+    // in storage.rs
+    //     an input starting with 0x01 will induce a storage write
+
+    let filename = "tests/multicall/target/wasm32-unknown-unknown/release/multicall.wasm";
+    let (compile, config, ink) = test_configs();
+
+    let store_addr = random_bytes20();
+    let (key, value) = (random_bytes32(), random_bytes32());
+    let (mut native, mut evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    evm.deploy(store_addr, config, "storage")?;
+
+    let mut call = vec![0x02];
+    call.extend(store_addr);
+    call.push(0x01);
+    call.extend(key);
+    call.extend(value);
+
+    let mut args = vec![1];
+    args.extend(u32::to_be_bytes(call.len() as u32));
+    args.extend(call);
+
+    let outcome = native.run_main(&args, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Revert);
+    assert_eq!(evm.get_bytes32(key).0, Bytes32::default());
+    Ok(())
+}
+
+#[test]
+fn test_static_log() -> Result<()> {
+    // in multicall.rs
+    //     a kind of 0x02 makes a static call
+    //
+    // in log.rs
+    //     the first byte is the number of topics
+    //     the rest is the topics followed by the data
+
+    let filename = "tests/multicall/target/wasm32-unknown-unknown/release/multicall.wasm";
+    let (compile, config, ink) = test_configs();
+
+    let log_addr = random_bytes20();
+    let (mut native, mut evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    evm.deploy(log_addr, config, "log")?;
+
+    let mut call = vec![0x02];
+    call.extend(log_addr);
+    call.push(0x01);
+    call.extend(random_bytes32());
+
+    let mut args = vec![1];
+    args.extend(u32::to_be_bytes(call.len() as u32));
+    args.extend(call);
+
+    let outcome = native.run_main(&args, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Revert);
+    assert!(evm.logs().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_call_failure() -> Result<()> {
+    // in multicall.rs
+    //     a kind of 0x00 makes a normal call, reverting if it fails
+    //     a kind of 0x04 makes a normal call, continuing if it fails
+    //
+    // in fallible.rs
+    //     an input starting with 0x00 will execute an unreachable
+    //     other inputs are returned as the output
+
+    let filename = "tests/multicall/target/wasm32-unknown-unknown/release/multicall.wasm";
+    let (compile, config, ink) = test_configs();
+
+    let fallible = random_bytes20();
+    let (mut native, mut evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    evm.deploy(fallible, config, "fallible")?;
+
+    let call = |kind: u8, data: &[u8]| {
+        let mut call = vec![kind];
+        call.extend(Bytes32::default());
+        call.extend(fallible);
+        call.extend(data);
+
+        let mut action = u32::to_be_bytes(call.len() as u32).to_vec();
+        action.extend(call);
+        action
+    };
+
+    // the caller survives a callee's trap when it opts to
+    let mut args = vec![2];
+    args.extend(call(0x04, &[0x00]));
+    args.extend(call(0x00, &[0x01, 0x02]));
+    assert_eq!(run_native(&mut native, &args, ink)?, vec![0x01, 0x02]);
+
+    // otherwise the failure propagates as a revert
+    let mut args = vec![1];
+    args.extend(call(0x00, &[0x00]));
+    let outcome = native.run_main(&args, config, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::Revert);
+    Ok(())
+}
+
+#[test]
+fn test_call_gas() -> Result<()> {
+    // in exit-early.wat
+    //     the input is returned as the output
+    //     the status code is the first byte
+
+    let (compile, config, _) = test_configs();
+    let (mut evm, _) = TestEvmApi::new(compile);
+    let callee = random_bytes20();
+    evm.deploy_file(callee, config, "tests/exit-early/exit-early.wat")?;
+
+    let gas = 1_000_000;
+    let value = Bytes32::default();
+
+    // unspent gas is returned to the caller
+    let (len, cost, status) = evm.contract_call(callee, &[0x00; 32], gas, gas, value);
+    assert_eq!((len, status), (32, UserOutcomeKind::Success));
+    assert!(cost > 0 && cost < gas);
+
+    // reverts do too, and their data is retrievable by the caller
+    let args = [0x01; 32];
+    let (len, cost, status) = evm.contract_call(callee, &args, gas, gas, value);
+    assert_eq!((len, status), (32, UserOutcomeKind::Revert));
+    assert!(cost > 0 && cost < gas);
+    assert_eq!(evm.get_return_data().slice(), args);
+
+    // running out consumes everything forwarded
+    let (_, cost, status) = evm.contract_call(callee, &args, gas, 10, value);
+    assert_eq!((cost, status), (10, UserOutcomeKind::OutOfInk));
+    Ok(())
+}
+
+#[test]
+fn test_accounts() -> Result<()> {
+    // in accounts.wat
+    //     the balance, codehash, and code size of an account are written to the output,
+    //     followed by the requested range of its code
+
+    let (compile, config, ink) = test_configs();
+    let (mut evm, evm_data) = TestEvmApi::new(compile.clone());
+    let account = random_bytes20();
+    let balance = random_bytes32();
+    let code: Vec<u8> = (0..64).collect();
+    evm.set_account(account, balance, code.clone());
+
+    let run = |address: Bytes20, offset: u32, size: u32, ink: u64| -> Result<UserOutcome> {
+        let mut args = address.to_vec();
+        args.extend(offset.to_le_bytes());
+        args.extend(size.to_le_bytes());
+
+        let file = "tests/accounts.wat";
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        native.run_main(&args, config, ink)
+    };
+    let check = |address: Bytes20, offset: u32, size: u32, expected: &[u8]| -> Result<Vec<u8>> {
+        let UserOutcome::Success(output) = run(address, offset, size, ink)? else {
+            bail!("account queries failed")
+        };
+        let word = |i: usize| u32::from_le_bytes(output[i..i + 4].try_into().unwrap());
+        assert_eq!(word(68), expected.len() as u32);
+        assert_eq!(&output[72..], expected);
+        Ok(output)
+    };
+
+    let output = check(account, 0, 64, &code)?;
+    assert_eq!(&output[..32], balance.as_slice());
+    assert_eq!(&output[32..64], crypto::keccak(&code));
+    assert_eq!(u32::from_le_bytes(output[64..68].try_into().unwrap()), 64);
+
+    check(account, 8, 16, &code[8..24])?;
+    check(account, 48, 32, &code[48..])?; // partial reads copy only the overlap
+    check(account, 80, 8, &[])?; // out of bounds reads copy nothing
+
+    // unknown accounts are empty
+    let output = check(random_bytes20(), 0, 32, &[])?;
+    assert_eq!(&output[..68], &[0; 68]);
+
+    // the first query of an account costs a cold access
+    let ink = config.pricing.gas_to_ink(COLD_ACCOUNT_GAS);
+    let outcome = run(random_bytes20(), 0, 64, ink)?;
+    assert_eq!(outcome.kind(), UserOutcomeKind::OutOfInk);
+    Ok(())
+}
+
+#[test]
+fn test_account_warmth() -> Result<()> {
+    // in accounts.wat
+    //     the account is queried four times: balance, codehash, code size, and code
+
+    let (compile, config, ink) = test_configs();
+    let (mut evm, evm_data) = TestEvmApi::new(compile.clone());
+    let account = random_bytes20();
+    evm.set_account(account, random_bytes32(), vec![0; 32]);
+
+    let mut args = account.to_vec();
+    args.extend([0; 8]);
+
+    let ink_used = || -> Result<u64> {
+        let file = "tests/accounts.wat";
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        run_native(&mut native, &args, ink)?;
+        Ok(ink - native.ink_ready()?)
+    };
+
+    // only the first access is cold, even across calls
+    let cold = ink_used()?;
+    let warm = ink_used()?;
+    let savings = COLD_ACCOUNT_GAS - WARM_SLOAD_GAS;
+    assert_eq!(cold - warm, config.pricing.gas_to_ink(savings));
+    assert_eq!(warm, ink_used()?);
+    Ok(())
+}
+
+#[test]
+fn test_return_data() -> Result<()> {
+    // in return-data.wat
+    //     the callee is called with the rest of the args, then the return data
+    //     is copied at the requested offset and size
+    //
+    // in exit-early.wat
+    //     the input is returned as the output
+
+    let (compile, config, ink) = test_configs();
+    let callee = random_bytes20();
+    let (mut evm, evm_data) = TestEvmApi::new(compile.clone());
+    evm.deploy_file(callee, config, "tests/exit-early/exit-early.wat")?;
+
+    let calldata: Vec<u8> = (0..32).collect();
+    let check = |offset: u32, size: u32, expected: &[u8]| -> Result<()> {
+        let mut args = callee.to_vec();
+        args.extend(offset.to_le_bytes());
+        args.extend(size.to_le_bytes());
+        args.extend(&calldata);
+
+        let file = "tests/return-data.wat";
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        let output = run_native(&mut native, &args, ink)?;
+        let word = |i: usize| u32::from_le_bytes(output[4 * i..4 * i + 4].try_into().unwrap());
+        assert_eq!(word(0), 0); // nothing to read before the call
+        assert_eq!(word(1), 32);
+        assert_eq!(word(2), expected.len() as u32);
+        assert_eq!(&output[12..], expected);
+        Ok(())
+    };
+
+    check(0, 32, &calldata)?;
+    check(8, 4, &calldata[8..12])?;
+    check(16, 32, &calldata[16..])?; // partial reads copy only the overlap
+    check(40, 8, &[])?; // out of bounds reads copy nothing
+    check(0, 0, &[])
+}
+
+#[test]
+fn test_return_data_chunks() -> Result<()> {
+    // in return-data-chunks.wat
+    //     the callee is called with the rest of the args, then the return data
+    //     is read back in chunks of the given size until a read comes back empty
+    //
+    // in exit-early.wat
+    //     the input is returned as the output
+
+    let (compile, config, ink) = test_configs();
+    let callee = random_bytes20();
+    let (mut evm, evm_data) = TestEvmApi::new(compile.clone());
+    evm.deploy_file(callee, config, "tests/exit-early/exit-early.wat")?;
+
+    let calldata: Vec<u8> = (0..100).collect();
+    for chunk in [1_u32, 7, 32, 100, 256] {
+        let mut args = callee.to_vec();
+        args.extend(chunk.to_le_bytes());
+        args.extend(&calldata);
+
+        let file = "tests/return-data-chunks.wat";
+        let mut native = TestInstance::from_path(file, evm.clone(), evm_data, &compile, config)?;
+        let output = run_native(&mut native, &args, ink)?;
+        let reads = u32::from_le_bytes(output[..4].try_into().unwrap());
+        assert_eq!(reads, (100 + chunk - 1) / chunk + 1); // the last read is empty
+        assert_eq!(&output[4..], calldata);
+    }
+    Ok(())
+}
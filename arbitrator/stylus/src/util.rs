@@ -3,6 +3,21 @@
 
 use arbutil::crypto;
 use eyre::Report;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    process,
+};
+
+/// Runs the body of an FFI, aborting the process if it panics.
+///
+/// Unwinding out of an `extern "C"` function is undefined behavior on our toolchain,
+/// so fatal errors like those of [`panic_with_wasm`] must abort explicitly.
+pub fn abort_on_panic<T>(body: impl FnOnce() -> T) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(value) => value,
+        Err(_) => process::abort(),
+    }
+}
 
 /// This function panics while saving an offending wasm to disk.
 pub fn panic_with_wasm(wasm: &[u8], error: Report) -> ! {
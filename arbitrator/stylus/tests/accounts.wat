@@ -0,0 +1,29 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"         (func $read_args         (param i32)))
+    (import "vm_hooks" "write_result"      (func $write_result      (param i32 i32)))
+    (import "vm_hooks" "account_balance"   (func $account_balance   (param i32 i32)))
+    (import "vm_hooks" "account_codehash"  (func $account_codehash  (param i32 i32)))
+    (import "vm_hooks" "account_code_size" (func $account_code_size (param i32) (result i32)))
+    (import "vm_hooks" "account_code"      (func $account_code      (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; the args are an address followed by the offset and size of the code to copy
+        (call $read_args (i32.const 0))
+
+        (call $account_balance  (i32.const 0) (i32.const 1024))
+        (call $account_codehash (i32.const 0) (i32.const 1056))
+        (i32.store (i32.const 1088) (call $account_code_size (i32.const 0)))
+
+        ;; copy the requested range to 1096, along with the number of bytes copied
+        (i32.store (i32.const 1092)
+            (call $account_code
+                (i32.const 0) (i32.load (i32.const 20)) (i32.load (i32.const 24)) (i32.const 1096)))
+
+        ;; write everything out
+        (call $write_result (i32.const 1024) (i32.add (i32.const 72) (i32.load (i32.const 1092))))
+        i32.const 0
+    )
+)
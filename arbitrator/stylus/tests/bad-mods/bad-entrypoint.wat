@@ -0,0 +1,7 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (memory (export "memory") 0 0)
+    (func (export "arbitrum_main") (param i32) (result i32)
+        i32.const 0))
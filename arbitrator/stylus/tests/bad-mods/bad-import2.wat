@@ -0,0 +1,8 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args" (func (param i64)))
+    (memory (export "memory") 0 0)
+    (func (export "user_entrypoint") (param i32) (result i32)
+        i32.const 0))
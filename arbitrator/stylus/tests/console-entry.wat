@@ -0,0 +1,11 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "console" "log_i32" (func $log_i32 (param i32)))
+    (memory (export "memory") 0 0)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        (call $log_i32 (local.get $args_len))
+        i32.const 0
+    )
+)
@@ -0,0 +1,23 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"    (func $read_args    (param i32)))
+    (import "vm_hooks" "create1"      (func $create1      (param i32 i32 i32 i32 i32)))
+    (import "vm_hooks" "write_result" (func $write_result (param i32 i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; the args are the endowment followed by the init code
+        (call $read_args (i32.const 0))
+
+        ;; deploy, writing the address and then the revert data length
+        (call $create1
+            (i32.const 32)
+            (i32.sub (local.get $args_len) (i32.const 32))
+            (i32.const 0)
+            (i32.const 0x1000)
+            (i32.const 0x1014))
+        (call $write_result (i32.const 0x1000) (i32.const 24))
+        i32.const 0
+    )
+)
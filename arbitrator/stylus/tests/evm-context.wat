@@ -0,0 +1,43 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "write_result"     (func $write_result     (param i32 i32)))
+    (import "vm_hooks" "msg_sender"       (func $msg_sender       (param i32)))
+    (import "vm_hooks" "contract_address" (func $contract_address (param i32)))
+    (import "vm_hooks" "tx_origin"        (func $tx_origin        (param i32)))
+    (import "vm_hooks" "block_coinbase"   (func $block_coinbase   (param i32)))
+    (import "vm_hooks" "msg_value"        (func $msg_value        (param i32)))
+    (import "vm_hooks" "block_basefee"    (func $block_basefee    (param i32)))
+    (import "vm_hooks" "tx_gas_price"     (func $tx_gas_price     (param i32)))
+    (import "vm_hooks" "block_number"     (func $block_number     (result i64)))
+    (import "vm_hooks" "chainid"          (func $chainid          (result i64)))
+    (import "vm_hooks" "block_timestamp"  (func $block_timestamp  (result i64)))
+    (import "vm_hooks" "block_gas_limit"  (func $block_gas_limit  (result i64)))
+    (import "vm_hooks" "msg_reentrant"    (func $msg_reentrant    (result i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; addresses
+        (call $msg_sender       (i32.const 0))
+        (call $contract_address (i32.const 20))
+        (call $tx_origin        (i32.const 40))
+        (call $block_coinbase   (i32.const 60))
+
+        ;; words
+        (call $msg_value     (i32.const 80))
+        (call $block_basefee (i32.const 112))
+        (call $tx_gas_price  (i32.const 144))
+
+        ;; little-endian u64s
+        (i64.store (i32.const 176) (call $block_number))
+        (i64.store (i32.const 184) (call $chainid))
+        (i64.store (i32.const 192) (call $block_timestamp))
+        (i64.store (i32.const 200) (call $block_gas_limit))
+
+        ;; little-endian u32s
+        (i32.store (i32.const 208) (call $msg_reentrant))
+
+        (call $write_result (i32.const 0) (i32.const 212))
+        i32.const 0
+    )
+)
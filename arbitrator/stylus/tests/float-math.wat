@@ -0,0 +1,37 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"    (func $read_args    (param i32)))
+    (import "vm_hooks" "write_result" (func $write_result (param i32 i32)))
+    (memory (export "memory") 1 1)
+    (func $store (param $index i32) (param $value f64)
+        (f64.store
+            (i32.add (i32.const 16) (i32.mul (local.get $index) (i32.const 8)))
+            (local.get $value)))
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        (local $a f64)
+        (local $b f64)
+
+        ;; read two f64s from the args
+        (call $read_args (i32.const 0))
+        (local.set $a (f64.load (i32.const 0)))
+        (local.set $b (f64.load (i32.const 8)))
+
+        ;; write the result of each op after them
+        (call $store (i32.const 0) (f64.add (local.get $a) (local.get $b)))
+        (call $store (i32.const 1) (f64.sub (local.get $a) (local.get $b)))
+        (call $store (i32.const 2) (f64.mul (local.get $a) (local.get $b)))
+        (call $store (i32.const 3) (f64.div (local.get $a) (local.get $b)))
+        (call $store (i32.const 4) (f64.sqrt (f64.abs (local.get $a))))
+        (call $store (i32.const 5) (f64.min (local.get $a) (local.get $b)))
+        (call $store (i32.const 6) (f64.max (local.get $a) (local.get $b)))
+        (call $store (i32.const 7) (f64.nearest (f64.div (local.get $a) (local.get $b))))
+        (call $store (i32.const 8) (f64.floor (local.get $a)))
+        (call $store (i32.const 9) (f64.ceil (local.get $b)))
+        (call $store (i32.const 10) (f64.promote_f32 (f32.demote_f64 (local.get $a))))
+        (call $store (i32.const 11) (f64.convert_i64_s (i64.reinterpret_f64 (local.get $b))))
+        (call $write_result (i32.const 16) (i32.const 96))
+        i32.const 0
+    )
+)
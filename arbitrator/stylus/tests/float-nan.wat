@@ -0,0 +1,18 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "write_result" (func $write_result (param i32 i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; write the bits of NaNs produced by arithmetic, whose payloads wasm leaves unspecified
+        (i64.store (i32.const 0)
+            (i64.reinterpret_f64 (f64.div (f64.const 0) (f64.const 0))))
+        (i64.store (i32.const 8)
+            (i64.reinterpret_f64 (f64.add (f64.const nan:0x4) (f64.const 1))))
+        (i32.store (i32.const 16)
+            (i32.reinterpret_f32 (f32.sqrt (f32.const -1))))
+        (call $write_result (i32.const 0) (i32.const 20))
+        i32.const 0
+    )
+)
@@ -0,0 +1,18 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "write_result" (func $write_result (param i32 i32)))
+    (import "vm_hooks" "evm_ink_left" (func $evm_ink_left (result i64)))
+    (import "vm_hooks" "evm_gas_left" (func $evm_gas_left (result i64)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; write the ink left twice, then the gas left, then the ink left once more
+        (i64.store (i32.const 0)  (call $evm_ink_left))
+        (i64.store (i32.const 8)  (call $evm_ink_left))
+        (i64.store (i32.const 16) (call $evm_gas_left))
+        (i64.store (i32.const 24) (call $evm_ink_left))
+        (call $write_result (i32.const 0) (i32.const 32))
+        i32.const 0
+    )
+)
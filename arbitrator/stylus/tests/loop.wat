@@ -0,0 +1,14 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (memory (export "memory") 0 0)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; loop once per byte of args
+        (loop $loop
+            (local.set $args_len (i32.sub (local.get $args_len) (i32.const 1)))
+            (br_if $loop (local.get $args_len))
+        )
+        i32.const 0
+    )
+)
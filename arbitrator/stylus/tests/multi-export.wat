@@ -0,0 +1,20 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "write_result" (func $write_result (param i32 i32)))
+    (memory (export "memory") 1 1)
+    (data (i32.const 0) "main")
+    (data (i32.const 4) "view")
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        (call $write_result (i32.const 0) (i32.const 4))
+        i32.const 0
+    )
+    (func (export "view") (param $args_len i32) (result i32)
+        (call $write_result (i32.const 4) (i32.const 4))
+        i32.const 0
+    )
+    (func (export "wrong_shape") (result i32)
+        i32.const 0
+    )
+)
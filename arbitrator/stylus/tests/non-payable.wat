@@ -0,0 +1,17 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "msg_value" (func $msg_value (param i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        (call $msg_value (i32.const 0))
+
+        ;; revert if any word of the value is nonzero
+        (i64.or
+            (i64.or (i64.load (i32.const 0)) (i64.load (i32.const 8)))
+            (i64.or (i64.load (i32.const 16)) (i64.load (i32.const 24))))
+        i64.const 0
+        i64.ne
+    )
+)
@@ -0,0 +1,17 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (memory (export "memory") 0 0)
+    (func $recurse (param $depth i32)
+        ;; call ourselves until the depth reaches 0
+        (if (local.get $depth)
+            (then (call $recurse (i32.sub (local.get $depth) (i32.const 1))))
+        )
+    )
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; recurse once per byte of args
+        (call $recurse (local.get $args_len))
+        i32.const 0
+    )
+)
@@ -0,0 +1,41 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"        (func $read_args        (param i32)))
+    (import "vm_hooks" "write_result"     (func $write_result     (param i32 i32)))
+    (import "vm_hooks" "call_contract"    (func $call_contract    (param i32 i32 i32 i32 i64 i32) (result i32)))
+    (import "vm_hooks" "read_return_data" (func $read_return_data (param i32 i32 i32) (result i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        (local $offset i32)
+        (local $copied i32)
+        (local $reads i32)
+
+        ;; the args are the callee's address, a chunk size, and the calldata
+        (call $read_args (i32.const 0))
+
+        ;; call with all the gas, a value of zero at 1024, and the result length at 1056
+        (call $call_contract
+            (i32.const 0) (i32.const 24) (i32.sub (local.get $args_len) (i32.const 24))
+            (i32.const 1024) (i64.const -1) (i32.const 1056))
+        drop
+
+        ;; read chunks into 2048 until one comes back empty
+        (loop $chunk
+            (local.set $copied
+                (call $read_return_data
+                    (i32.add (i32.const 2048) (local.get $offset))
+                    (local.get $offset)
+                    (i32.load (i32.const 20))))
+            (local.set $offset (i32.add (local.get $offset) (local.get $copied)))
+            (local.set $reads (i32.add (local.get $reads) (i32.const 1)))
+            (br_if $chunk (local.get $copied))
+        )
+
+        ;; write the number of reads followed by the reassembled data
+        (i32.store (i32.const 2044) (local.get $reads))
+        (call $write_result (i32.const 2044) (i32.add (i32.const 4) (local.get $offset)))
+        i32.const 0
+    )
+)
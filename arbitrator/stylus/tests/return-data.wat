@@ -0,0 +1,36 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"        (func $read_args        (param i32)))
+    (import "vm_hooks" "write_result"     (func $write_result     (param i32 i32)))
+    (import "vm_hooks" "call_contract"    (func $call_contract    (param i32 i32 i32 i32 i64 i32) (result i32)))
+    (import "vm_hooks" "return_data_size" (func $return_data_size (result i32)))
+    (import "vm_hooks" "read_return_data" (func $read_return_data (param i32 i32 i32) (result i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; the args are the callee's address, an offset, a size, and the calldata
+        (call $read_args (i32.const 0))
+
+        ;; the size before any calls
+        (i32.store (i32.const 2048) (call $return_data_size))
+
+        ;; call with all the gas, a value of zero at 1024, and the result length at 1056
+        (call $call_contract
+            (i32.const 0) (i32.const 28) (i32.sub (local.get $args_len) (i32.const 28))
+            (i32.const 1024) (i64.const -1) (i32.const 1056))
+        drop
+
+        ;; the size after the call
+        (i32.store (i32.const 2052) (call $return_data_size))
+
+        ;; copy the requested range to 2060, along with the number of bytes copied
+        (i32.store (i32.const 2056)
+            (call $read_return_data
+                (i32.const 2060) (i32.load (i32.const 20)) (i32.load (i32.const 24))))
+
+        ;; write the sizes and copied bytes
+        (call $write_result (i32.const 2048) (i32.add (i32.const 12) (i32.load (i32.const 2056))))
+        i32.const 0
+    )
+)
@@ -0,0 +1,13 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (memory (export "memory") 0 0)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; loop forever
+        (loop $loop
+            br $loop
+        )
+        i32.const 0
+    )
+)
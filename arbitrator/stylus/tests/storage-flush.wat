@@ -0,0 +1,18 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"             (func $read_args   (param i32)))
+    (import "vm_hooks" "storage_cache_bytes32" (func $cache_bytes (param i32 i32)))
+    (import "vm_hooks" "storage_flush_cache"   (func $flush_cache (param i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; the args are a key followed by the value to store there
+        (call $read_args (i32.const 0))
+        (call $cache_bytes (i32.const 0) (i32.const 32))
+
+        ;; persist the write, keeping the cache
+        (call $flush_cache (i32.const 0))
+        i32.const 0
+    )
+)
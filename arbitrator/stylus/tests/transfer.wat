@@ -0,0 +1,20 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args"     (func $read_args     (param i32)))
+    (import "vm_hooks" "call_contract" (func $call_contract (param i32 i32 i32 i32 i64 i32) (result i32)))
+    (import "vm_hooks" "write_result"  (func $write_result  (param i32 i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; the args are an address followed by the value to send it
+        (call $read_args (i32.const 0))
+
+        ;; call with no calldata and no gas, leaving only the stipend, then return the status
+        (i32.store8 (i32.const 0x100)
+            (call $call_contract
+                (i32.const 0) (i32.const 52) (i32.const 0) (i32.const 20) (i64.const 0) (i32.const 0x104)))
+        (call $write_result (i32.const 0x100) (i32.const 1))
+        i32.const 0
+    )
+)
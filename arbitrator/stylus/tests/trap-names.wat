@@ -0,0 +1,12 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (memory (export "memory") 0 0)
+    (func $deeply_nested_failure
+        unreachable)
+    (func $user_entrypoint (export "user_entrypoint") (param $args_len i32) (result i32)
+        call $deeply_nested_failure
+        i32.const 0
+    )
+)
@@ -0,0 +1,24 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "vm_hooks" "read_args" (func $read_args (param i32)))
+    (memory (export "memory") 1 1)
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        ;; the first byte of the args selects the trap
+        (call $read_args (i32.const 0))
+        (block $overflow
+            (block $out_of_bounds
+                (block $div_by_zero
+                    (block $unreachable
+                        (br_table $unreachable $div_by_zero $out_of_bounds $overflow
+                            (i32.load8_u (i32.const 0))))
+                    unreachable)
+                (drop (i32.div_u (i32.const 1) (i32.const 0)))
+                (return (i32.const 0)))
+            (drop (i32.load (i32.const 65536)))
+            (return (i32.const 0)))
+        (drop (i32.div_s (i32.const -2147483648) (i32.const -1)))
+        i32.const 0
+    )
+)
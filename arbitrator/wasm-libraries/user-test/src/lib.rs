@@ -4,7 +4,7 @@
 #![allow(clippy::missing_safety_doc)]
 
 use arbutil::{evm::EvmData, Bytes32};
-use fnv::FnvHashMap as HashMap;
+use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
 use prover::programs::prelude::StylusConfig;
@@ -22,6 +22,7 @@ pub(crate) static mut EVER_PAGES: u16 = 0;
 
 lazy_static! {
     static ref KEYS: Mutex<HashMap<Bytes32, Bytes32>> = Mutex::new(HashMap::default());
+    static ref WARM_KEYS: Mutex<HashSet<Bytes32>> = Mutex::new(HashSet::default());
     static ref EVM_DATA: EvmData = EvmData::default();
 }
 
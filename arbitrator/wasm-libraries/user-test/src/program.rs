@@ -1,12 +1,12 @@
 // Copyright 2022-2024, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
-use crate::{ARGS, EVER_PAGES, EVM_DATA, KEYS, LOGS, OPEN_PAGES, OUTS};
+use crate::{ARGS, EVER_PAGES, EVM_DATA, KEYS, LOGS, OPEN_PAGES, OUTS, WARM_KEYS};
 use arbutil::{
     evm::{
         api::{EvmApi, VecReader},
         user::UserOutcomeKind,
-        EvmData,
+        EvmData, COLD_SLOAD_GAS, WARM_SLOAD_GAS,
     },
     Bytes20, Bytes32, Color,
 };
@@ -104,11 +104,16 @@ pub struct MockEvmApi;
 impl EvmApi<VecReader> for MockEvmApi {
     fn get_bytes32(&mut self, key: Bytes32) -> (Bytes32, u64) {
         let value = KEYS.lock().get(&key).cloned().unwrap_or_default();
-        (value, 2100) // pretend worst case
+        let cost = match WARM_KEYS.lock().insert(key) {
+            true => COLD_SLOAD_GAS,
+            false => WARM_SLOAD_GAS,
+        };
+        (value, cost)
     }
 
     fn cache_bytes32(&mut self, key: Bytes32, value: Bytes32) -> u64 {
         KEYS.lock().insert(key, value);
+        WARM_KEYS.lock().insert(key);
         0
     }
 
@@ -247,7 +247,14 @@ func evictProgram(db vm.StateDB, module common.Hash, version uint16, debug bool,
 	}
 }
 
+// The version of the stylus FFI this package was written against.
+// Must match STYLUS_ABI_VERSION in arbitrator/stylus/src/lib.rs.
+const stylusAbiVersion = 1
+
 func init() {
+	if version := uint32(C.stylus_abi_version()); version != stylusAbiVersion {
+		panic(fmt.Sprintf("stylus ABI mismatch: library has version %v, expected %v", version, stylusAbiVersion))
+	}
 	state.CacheWasmRust = func(asm []byte, moduleHash common.Hash, version uint16, tag uint32, debug bool) {
 		C.stylus_cache_module(goSlice(asm), hashToBytes32(moduleHash), u16(version), u32(tag), cbool(debug))
 	}